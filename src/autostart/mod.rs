@@ -0,0 +1,177 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use log::{error, info, warn};
+
+use crate::{
+    config::{self, StartupProgram},
+    process::{Exited, Pid, Processes},
+};
+
+// A crashing program is not started again more often than this
+const RESPAWN_DELAY: Duration = Duration::from_secs(2);
+
+/// Programs started with tdawm, running in the background.
+#[derive(Default)]
+pub struct Autostart {
+    programs: Vec<Program>,
+}
+
+struct Program {
+    program: StartupProgram,
+    pid: Option<Pid>,
+    started_at: Instant,
+    // exited with an error or could not be started
+    crashed: bool,
+}
+
+impl Autostart {
+    /// Starts every program, to be called once when tdawm starts.
+    pub fn start(&mut self, programs: &[StartupProgram], processes: &mut Processes) {
+        info!("running startup");
+        for program in programs.iter() {
+            self.spawn(program.clone(), processes);
+        }
+    }
+    /// Starts the programs marked `on_reload` again (when they are not
+    /// running anymore) and stops supervising the ones removed from the config.
+    pub fn reload(&mut self, programs: &[StartupProgram], processes: &mut Processes) {
+        self.programs
+            .retain(|p| programs.iter().any(|n| n.command == p.program.command));
+        for program in programs.iter() {
+            let running = self
+                .programs
+                .iter_mut()
+                .find(|p| p.program.command == program.command);
+            match running {
+                Some(running) => {
+                    running.program = program.clone();
+                    if program.on_reload && running.pid.is_none() {
+                        running.restart(processes);
+                    }
+                }
+                None if program.on_reload => self.spawn(program.clone(), processes),
+                None => {}
+            }
+        }
+    }
+    /// Handles the exited programs and restarts the crashed ones which asked for it.
+    pub fn supervise(&mut self, exited: &[Exited], processes: &mut Processes) {
+        for exit in exited.iter() {
+            let Some(program) = self.programs.iter_mut().find(|p| p.pid == Some(exit.pid)) else {
+                continue;
+            };
+            program.pid = None;
+            if exit.status.success() {
+                info!("{} exited", program.program.command);
+            } else {
+                warn!("{} exited with {}", program.program.command, exit.status);
+                program.crashed = true;
+            }
+        }
+        for program in self.programs.iter_mut() {
+            if program.pid.is_none()
+                && program.crashed
+                && program.program.respawn
+                && program.started_at.elapsed() >= RESPAWN_DELAY
+            {
+                program.restart(processes);
+            }
+        }
+        // Programs which are done are not needed anymore
+        self.programs
+            .retain(|p| p.pid.is_some() || (p.crashed && p.program.respawn));
+    }
+    fn spawn(&mut self, program: StartupProgram, processes: &mut Processes) {
+        let mut program = Program {
+            program,
+            pid: None,
+            started_at: Instant::now(),
+            crashed: false,
+        };
+        program.restart(processes);
+        self.programs.push(program);
+    }
+}
+
+impl Program {
+    fn restart(&mut self, processes: &mut Processes) {
+        self.started_at = Instant::now();
+        let output = log_file(&self.program);
+        match processes.spawn_with_output(&self.program.command, output) {
+            Ok(pid) => {
+                self.pid = Some(pid);
+                self.crashed = false;
+            }
+            Err(e) => {
+                error!("unable to start {}: {}", self.program.command, e);
+                self.pid = None;
+                self.crashed = true;
+            }
+        }
+    }
+}
+
+// Output of each program goes to <state dir>/startup/<command>.log
+fn log_file(program: &StartupProgram) -> Option<File> {
+    let dir = config::state_dir()?.join("startup");
+    if let Err(e) = fs::create_dir_all(&dir) {
+        warn!("unable to create {}: {}", dir.display(), e);
+        return None;
+    }
+    let path = dir.join(format!("{}.log", log_name(&program.command)));
+    match OpenOptions::new().create(true).append(true).open(&path) {
+        Ok(file) => Some(file),
+        Err(e) => {
+            warn!("unable to open {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+// Name of the executable followed by the arguments, so the same program
+// started with other arguments gets its own log. Characters which don't
+// belong in a file name are replaced by _.
+fn log_name(command: &str) -> String {
+    let command = command.trim();
+    let (executable, arguments) = command
+        .split_once(char::is_whitespace)
+        .unwrap_or((command, ""));
+    let mut name = Path::new(executable)
+        .file_name()
+        .map_or("unnamed".into(), |name| name.to_string_lossy().into_owned());
+    if !arguments.trim().is_empty() {
+        name.push('-');
+        name.extend(arguments.split_whitespace().flat_map(|argument| {
+            argument
+                .chars()
+                .map(|c| match c {
+                    'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '.' => c,
+                    _ => '_',
+                })
+                .chain(['_'])
+        }));
+        name.pop();
+    }
+    name.chars().take(128).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_names() {
+        assert_eq!(log_name("picom"), "picom");
+        assert_eq!(log_name("/usr/bin/polybar  main "), "polybar-main");
+        assert_eq!(
+            log_name("sh -c 'xset r rate 200'"),
+            "sh--c__xset_r_rate_200_"
+        );
+        assert_ne!(log_name("sh -c 'a'"), log_name("sh -c 'b'"));
+        assert_eq!(log_name("feh --bg-fill ~/w.png"), "feh---bg-fill___w.png");
+    }
+}
@@ -1,38 +1,636 @@
-use std::{env, fs, path::PathBuf};
+use std::{
+    collections::HashMap,
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
-use log::info;
-use serde::Deserialize;
+use log::warn;
+use log::{info, LevelFilter};
+use serde::{
+    de::{self, value::MapAccessDeserializer, Visitor},
+    Deserialize, Deserializer,
+};
 use thiserror::Error;
+use toml::Spanned;
+use x11::xlib;
+
+use crate::{layouts::LAYOUT_IDS, logger::LogSettings, tdawm::Action};
+
+mod watcher;
+pub use watcher::*;
+mod validate;
+pub use validate::*;
+
+const DEFAULT_CONFIG: &str = include_str!("default.toml");
 
 #[derive(Error, Debug)]
 pub enum ConfigError {
     #[error("unable to read config: {0}")]
     UnableToReadConfig(PathBuf),
     #[error("invalid toml file: {0}")]
-    TomlSyntaxError(String),
+    TomlSyntaxError(Diagnostic),
+    #[error("{0} has {1} error(s)")]
+    InvalidConfig(PathBuf, usize),
+    #[error("no config file found")]
+    ConfigNotFound,
     #[error("$HOME is not defined")]
     HomeNotDefined,
 }
 
 #[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
-    pub startup: Vec<String>,
+    #[serde(default)]
+    pub startup: Vec<StartupProgram>,
+    /// Modifier used by bindings written with "mod".
+    #[serde(default = "default_modifier")]
+    pub modifier: PrimaryModifier,
+    /// Replaces `modifier` when tdawm runs inside another X session (Xephyr),
+    /// so the host window manager keeps its own shortcuts.
+    #[serde(default = "default_nested_modifier")]
+    pub nested_modifier: PrimaryModifier,
+    #[serde(default = "default_keybindings")]
+    pub keybinding: Vec<Keybinding>,
+    #[serde(default)]
+    pub mode: Vec<ModeConfig>,
+    #[serde(default = "default_mousebindings")]
+    pub mousebinding: Vec<Mousebinding>,
+    /// What happens to a tiled window moved with the mouse.
+    #[serde(default)]
+    pub drag_tiled: DragTiled,
+    /// Where new windows are put in the stack of their workspace.
+    #[serde(default)]
+    pub new_window: NewWindow,
+    /// Seconds given to a window asked to close before its client is killed,
+    /// windows are never killed when it is not set.
+    #[serde(default)]
+    pub kill_timeout: Option<u64>,
+    /// Going to the current workspace goes back to the previous one.
+    #[serde(default)]
+    pub auto_back_and_forth: bool,
+    /// Reload the config when the file is modified.
+    #[serde(default)]
+    pub watch_config: bool,
+    #[serde(default)]
+    pub layout: LayoutConfig,
+    #[serde(default)]
+    pub log: LogConfig,
+}
+
+/// Initial settings of the layouts on every workspace,
+/// they can then be changed at runtime.
+/// ```toml
+/// [layout]
+/// default = "dwm"
+/// cycle = ["dwm", "horizontal", "vertical"]
+/// workspaces = { 3 = "vertical" }
+/// nmaster = 1
+/// mfact = 0.5
+/// ```
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields, default)]
+pub struct LayoutConfig {
+    /// Layout of the workspaces not listed in `workspaces`
+    pub default: Spanned<String>,
+    /// Layouts gone through by next-layout and prev-layout
+    pub cycle: Vec<Spanned<String>>,
+    /// Layout of some workspaces, by workspace number
+    pub workspaces: HashMap<String, Spanned<String>>,
+    /// Number of windows in the master area
+    pub nmaster: usize,
+    /// Part of the screen width given to the master area
+    pub mfact: Mfact,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        LayoutConfig {
+            default: Spanned::new(0..0, "dwm".into()),
+            cycle: LAYOUT_IDS
+                .iter()
+                .map(|id| Spanned::new(0..0, id.to_string()))
+                .collect(),
+            workspaces: HashMap::new(),
+            nmaster: 1,
+            mfact: Mfact(0.5),
+        }
+    }
+}
+
+impl LayoutConfig {
+    /// Layout id of the workspace with this number (starting at 1).
+    pub fn workspace_layout(&self, number: usize) -> &str {
+        self.workspaces
+            .get(&number.to_string())
+            .unwrap_or(&self.default)
+            .get_ref()
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(try_from = "f32")]
+pub struct Mfact(pub f32);
+
+impl Mfact {
+    pub const MIN: f32 = 0.05;
+    pub const MAX: f32 = 0.95;
+}
+
+impl TryFrom<f32> for Mfact {
+    type Error = String;
+
+    fn try_from(value: f32) -> Result<Self, Self::Error> {
+        if !(Mfact::MIN..=Mfact::MAX).contains(&value) {
+            return Err(format!(
+                "mfact must be between {} and {}, got {}",
+                Mfact::MIN,
+                Mfact::MAX,
+                value
+            ));
+        }
+        Ok(Mfact(value))
+    }
+}
+
+/// Where and what to log. Every setting can be overridden by an
+/// environment variable: TDAWM_LOG_PATH, LOG_LEVEL, TDAWM_LOG_MODULES
+/// (`x11=warn,layouts=trace`), TDAWM_LOG_MAX_SIZE and TDAWM_LOG_ROTATE.
+/// ```toml
+/// [log]
+/// path = "~/.local/state/tdawm/tdawm.log"
+/// level = "info"
+/// modules = { x11 = "warn", layouts = "trace" }
+/// max_size = 10485760
+/// rotate = 5
+/// stdout = true
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields, default)]
+pub struct LogConfig {
+    /// Defaults to tdawm.log in the state directory
+    pub path: Option<PathBuf>,
+    pub level: LogLevel,
+    /// Levels of some modules, by module path (`tdawm::x11` or `x11`)
+    pub modules: HashMap<String, LogLevel>,
+    /// Size in bytes after which the file is rotated
+    pub max_size: u64,
+    /// Number of rotated files kept
+    pub rotate: usize,
+    /// Also print logs on stdout
+    pub stdout: bool,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            path: None,
+            level: LogLevel(LevelFilter::Info),
+            modules: HashMap::new(),
+            max_size: 10 * 1024 * 1024,
+            rotate: 5,
+            stdout: true,
+        }
+    }
+}
+
+impl LogConfig {
+    /// Settings to give to the logger, environment variables applied.
+    pub fn settings(&self) -> LogSettings {
+        let mut settings = LogSettings {
+            path: self
+                .path
+                .as_ref()
+                .map(|p| expand_home(p))
+                .or_else(|| state_dir().map(|d| d.join("tdawm.log"))),
+            level: self.level.0,
+            modules: self
+                .modules
+                .iter()
+                .map(|(module, level)| (module_path(module), level.0))
+                .collect(),
+            max_size: self.max_size,
+            rotate: self.rotate,
+            stdout: self.stdout,
+        };
+        if let Ok(path) = env::var("TDAWM_LOG_PATH") {
+            settings.path = Some(expand_home(Path::new(&path)));
+        }
+        if let Some(level) = env_var("LOG_LEVEL") {
+            settings.level = level;
+        }
+        if let Ok(modules) = env::var("TDAWM_LOG_MODULES") {
+            settings.modules = modules
+                .split(',')
+                .filter_map(|filter| filter.split_once('='))
+                .filter_map(|(module, level)| Some((module_path(module), level.parse().ok()?)))
+                .collect();
+        }
+        if let Some(max_size) = env_var("TDAWM_LOG_MAX_SIZE") {
+            settings.max_size = max_size;
+        }
+        if let Some(rotate) = env_var("TDAWM_LOG_ROTATE") {
+            settings.rotate = rotate;
+        }
+        settings
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(try_from = "String")]
+pub struct LogLevel(pub LevelFilter);
+
+impl TryFrom<String> for LogLevel {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        LevelFilter::from_str(&value)
+            .map(LogLevel)
+            .map_err(|_| format!("unknown log level {}", value))
+    }
+}
+
+fn env_var<T: FromStr>(name: &str) -> Option<T> {
+    env::var(name).ok()?.trim().parse().ok()
+}
+
+// Module filters are matched without the crate name
+fn module_path(module: &str) -> String {
+    module.trim().trim_start_matches("tdawm::").to_string()
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), env::var("HOME")) {
+        (Ok(rest), Ok(home)) => Path::new(&home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+impl Config {
+    pub fn primary_modifier(&self) -> PrimaryModifier {
+        if running_nested() {
+            self.nested_modifier
+        } else {
+            self.modifier
+        }
+    }
+}
+
+// The justfile sets XEPHYR when starting tdawm inside Xephyr.
+fn running_nested() -> bool {
+    env::var("XEPHYR").is_ok()
+}
+
+/// A program started with tdawm, in the background.
+/// Either a command or a table with the supervision policy:
+/// ```toml
+/// startup = [
+///     "picom",
+///     { command = "polybar main", respawn = true },
+///     { command = "feh --bg-fill ~/wall.png", on_reload = true },
+/// ]
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StartupProgram {
+    pub command: String,
+    /// Start it again when it exits with an error
+    pub respawn: bool,
+    /// Start it again on every config reload, if it is not running
+    pub on_reload: bool,
+}
+
+// Not an untagged enum, its errors would lose the position of a bad key
+impl<'de> Deserialize<'de> for StartupProgram {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(StartupVisitor)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StartupTable {
+    command: String,
+    #[serde(default)]
+    respawn: bool,
+    #[serde(default)]
+    on_reload: bool,
+}
+
+struct StartupVisitor;
+
+impl<'de> Visitor<'de> for StartupVisitor {
+    type Value = StartupProgram;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a command or a table with a command")
+    }
+    fn visit_str<E: de::Error>(self, command: &str) -> Result<Self::Value, E> {
+        Ok(StartupProgram {
+            command: command.to_string(),
+            respawn: false,
+            on_reload: false,
+        })
+    }
+    fn visit_map<A: de::MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+        let table = StartupTable::deserialize(MapAccessDeserializer::new(map))?;
+        Ok(StartupProgram {
+            command: table.command,
+            respawn: table.respawn,
+            on_reload: table.on_reload,
+        })
+    }
+}
+
+/// A modifier+key combination bound to an action.
+/// The key is a keysym name (see X11/keysymdef.h without the `XK_` prefix)
+/// so bindings do not depend on the keyboard layout.
+/// ```toml
+/// [[keybinding]]
+/// modifiers = ["mod", "shift"]
+/// key = "Return"
+/// action = "spawn alacritty"
+/// ```
+/// Instead of an action, a binding can hold a chord: the bindings
+/// of the chord are used for the next key press only.
+/// ```toml
+/// [[keybinding]]
+/// modifiers = ["mod"]
+/// key = "w"
+/// [[keybinding.chord]]
+/// key = "v"
+/// action = "toggle-layout"
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Keybinding {
+    #[serde(default)]
+    pub modifiers: Vec<Modifier>,
+    pub key: Spanned<String>,
+    pub action: Option<Spanned<String>>,
+    #[serde(default)]
+    pub chord: Vec<Keybinding>,
+}
+
+/// Named set of keybindings activated with the `enter-mode` action.
+/// The keyboard is grabbed while a mode is active, Escape leaves
+/// the mode unless it is bound.
+/// ```toml
+/// [[mode]]
+/// name = "resize"
+/// [[mode.keybinding]]
+/// key = "Return"
+/// action = "exit-mode"
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ModeConfig {
+    pub name: Spanned<String>,
+    pub keybinding: Vec<Keybinding>,
 }
 
-pub fn load_config() -> Result<Config, ConfigError> {
-    let home_dir = match env::var("HOME") {
-        Ok(home_dir) => home_dir,
-        Err(_e) => return Err(ConfigError::HomeNotDefined),
+impl Keybinding {
+    /// The action of the binding, `None` when it has none or when it
+    /// is invalid (reported by `validate`).
+    pub fn action(&self) -> Option<Action> {
+        self.action.as_ref().and_then(|a| a.get_ref().parse().ok())
+    }
+    pub fn describe(&self) -> String {
+        let mut parts: Vec<String> = self
+            .modifiers
+            .iter()
+            .map(|m| format!("{:?}", m).to_lowercase())
+            .collect();
+        parts.push(self.key.get_ref().clone());
+        parts.join("+")
+    }
+    pub fn modifier_mask(&self, primary: PrimaryModifier) -> u32 {
+        modifiers_mask(&self.modifiers, primary)
+    }
+}
+
+/// A modifier+button combination used to drag windows.
+/// ```toml
+/// [[mousebinding]]
+/// modifiers = ["mod"]
+/// button = 1
+/// action = "move"
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Mousebinding {
+    #[serde(default)]
+    pub modifiers: Vec<Modifier>,
+    pub button: Spanned<u32>,
+    pub action: MouseAction,
+}
+
+impl Mousebinding {
+    pub fn modifier_mask(&self, primary: PrimaryModifier) -> u32 {
+        modifiers_mask(&self.modifiers, primary)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MouseAction {
+    Move,
+    /// Resizing a tiled window always makes it floating.
+    Resize,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DragTiled {
+    /// The window becomes floating
+    #[default]
+    Float,
+    /// The window takes the place of the tiled window it is dropped on
+    Swap,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum NewWindow {
+    /// The window becomes the master
+    #[default]
+    Master,
+    /// The window goes right after the focused one
+    AfterFocused,
+    /// The window goes at the bottom of the stack
+    End,
+}
+
+fn modifiers_mask(modifiers: &[Modifier], primary: PrimaryModifier) -> u32 {
+    modifiers.iter().fold(0, |mask, m| mask | m.mask(primary))
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Modifier {
+    /// The configured primary modifier
+    Mod,
+    Shift,
+    Ctrl,
+    Alt,
+    Super,
+}
+
+impl Modifier {
+    pub fn mask(&self, primary: PrimaryModifier) -> u32 {
+        match self {
+            Modifier::Mod => primary.mask(),
+            Modifier::Shift => xlib::ShiftMask,
+            Modifier::Ctrl => xlib::ControlMask,
+            Modifier::Alt => xlib::Mod1Mask,
+            Modifier::Super => xlib::Mod4Mask,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PrimaryModifier {
+    Super,
+    Alt,
+    Ctrl,
+}
+
+impl PrimaryModifier {
+    pub fn mask(&self) -> u32 {
+        match self {
+            PrimaryModifier::Super => xlib::Mod4Mask,
+            PrimaryModifier::Alt => xlib::Mod1Mask,
+            PrimaryModifier::Ctrl => xlib::ControlMask,
+        }
+    }
+}
+
+fn default_modifier() -> PrimaryModifier {
+    PrimaryModifier::Super
+}
+
+fn default_nested_modifier() -> PrimaryModifier {
+    PrimaryModifier::Alt
+}
+
+// The default config defines every binding, so these are never
+// called while parsing it.
+fn default_keybindings() -> Vec<Keybinding> {
+    default_config().keybinding
+}
+
+fn default_mousebindings() -> Vec<Mousebinding> {
+    default_config().mousebinding
+}
+
+pub fn default_config() -> Config {
+    parse_config(DEFAULT_CONFIG).expect("the built-in config is valid")
+}
+
+/// Where the config is read from: the path given on the command line,
+/// then `$XDG_CONFIG_HOME/tdawm/tdawm.toml`, then `~/.config/tdawm/tdawm.toml`.
+/// `None` means no file exists and the built-in config should be used.
+pub fn find_config(cli_path: Option<&Path>) -> Result<Option<PathBuf>, ConfigError> {
+    if let Some(path) = cli_path {
+        return Ok(Some(path.to_path_buf()));
+    }
+    let config_home = match env::var("XDG_CONFIG_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => match env::var("HOME") {
+            Ok(home_dir) => [home_dir.as_str(), ".config"].iter().collect(),
+            Err(_e) => return Err(ConfigError::HomeNotDefined),
+        },
+    };
+    let path = config_home.join("tdawm").join("tdawm.toml");
+    Ok(path.exists().then_some(path))
+}
+
+/// Where tdawm keeps its logs: `$XDG_STATE_HOME/tdawm`,
+/// or `~/.local/state/tdawm`.
+pub fn state_dir() -> Option<PathBuf> {
+    let state_home = match env::var("XDG_STATE_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => {
+            let home_dir = env::var("HOME").ok()?;
+            [home_dir.as_str(), ".local", "state"].iter().collect()
+        }
     };
+    Some(state_home.join("tdawm"))
+}
+
+pub fn load_config(path: Option<&Path>) -> Result<Config, ConfigError> {
+    load(path, false)
+}
+
+/// Like `load_config`, but a config that `check_config` would
+/// reject is an error, used when a config is already running.
+/// A missing file is an error too, the built-in config is only
+/// used at startup.
+pub fn load_valid_config(path: Option<&Path>) -> Result<Config, ConfigError> {
+    load(path, true)
+}
 
-    let config_path: PathBuf = [home_dir.as_str(), ".config", "tdawm", "tdawm.toml"]
-        .iter()
-        .collect();
-    let config_content = fs::read_to_string(config_path.clone())
-        .map_err(|_| ConfigError::UnableToReadConfig(config_path))?;
+fn load(path: Option<&Path>, strict: bool) -> Result<Config, ConfigError> {
+    let Some(path) = path else {
+        if strict {
+            return Err(ConfigError::ConfigNotFound);
+        }
+        info!("no config file found, using the built-in config");
+        return Ok(default_config());
+    };
+    let config_content = fs::read_to_string(path)
+        .map_err(|_| ConfigError::UnableToReadConfig(path.to_path_buf()))?;
 
-    let config: Config =
-        toml::from_str(&config_content).map_err(|e| ConfigError::TomlSyntaxError(e.to_string()))?;
-    info!("config loaded !");
+    let config = parse_config(&config_content)?;
+    let diagnostics = validate(&config, &config_content);
+    for diagnostic in diagnostics.iter() {
+        warn!("{}:{}", path.display(), diagnostic);
+    }
+    if strict && !diagnostics.is_empty() {
+        return Err(ConfigError::InvalidConfig(
+            path.to_path_buf(),
+            diagnostics.len(),
+        ));
+    }
+    info!("config loaded from {}", path.display());
     Ok(config)
 }
+
+/// Parses and validates a config file, the built-in
+/// config is checked when there is no file.
+pub fn check_config(path: Option<&Path>) -> Result<Vec<Diagnostic>, ConfigError> {
+    let content = match path {
+        Some(path) => fs::read_to_string(path)
+            .map_err(|_| ConfigError::UnableToReadConfig(path.to_path_buf()))?,
+        None => DEFAULT_CONFIG.to_string(),
+    };
+    match parse_config(&content) {
+        Ok(config) => Ok(validate(&config, &content)),
+        Err(ConfigError::TomlSyntaxError(diagnostic)) => Ok(vec![diagnostic]),
+        Err(e) => Err(e),
+    }
+}
+
+fn parse_config(content: &str) -> Result<Config, ConfigError> {
+    toml::from_str(content)
+        .map_err(|e| ConfigError::TomlSyntaxError(Diagnostic::from_toml_error(e, content)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_config_on_reload() {
+        assert!(load_config(None).is_ok());
+        assert!(matches!(
+            load_valid_config(None),
+            Err(ConfigError::ConfigNotFound)
+        ));
+        let path = env::temp_dir().join("tdawm-missing-config.toml");
+        assert!(matches!(
+            load_valid_config(Some(&path)),
+            Err(ConfigError::UnableToReadConfig(_))
+        ));
+    }
+}
@@ -0,0 +1,234 @@
+use std::{collections::HashSet, fmt};
+
+use toml::Spanned;
+
+use crate::{layouts::LAYOUT_IDS, tdawm::Action, x11::X11Adapter};
+
+use super::{Config, Keybinding};
+
+/// A problem found in a config file.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    // line and column, starting at 1
+    pub position: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn at(content: &str, offset: usize, message: String) -> Diagnostic {
+        let before = &content[..offset.min(content.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+        Diagnostic {
+            position: Some((line, column)),
+            message,
+        }
+    }
+    /// `content` is the text that failed to parse.
+    pub fn from_toml_error(error: toml::de::Error, content: &str) -> Diagnostic {
+        let message = error.message().trim_end().to_string();
+        match error.span() {
+            Some(span) => Diagnostic::at(content, span.start, message),
+            None => Diagnostic {
+                position: None,
+                message,
+            },
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.position {
+            Some((line, column)) => write!(f, "{}:{}: {}", line, column, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Semantic checks of a parsed config, `content` is the
+/// text it was parsed from.
+pub fn validate(config: &Config, content: &str) -> Vec<Diagnostic> {
+    let mut validator = Validator {
+        config,
+        content,
+        diagnostics: vec![],
+    };
+    validator.check_keybindings(&config.keybinding);
+    let mut modes = HashSet::new();
+    for mode in config.mode.iter() {
+        if !modes.insert(mode.name.get_ref()) {
+            validator.error(
+                mode.name.span().start,
+                format!("mode {} is defined twice", mode.name.get_ref()),
+            );
+        }
+        validator.check_keybindings(&mode.keybinding);
+    }
+    validator.check_layout(&config.layout.default);
+    for layout in config.layout.cycle.iter() {
+        validator.check_layout(layout);
+    }
+    for (number, layout) in config.layout.workspaces.iter() {
+        if !number.parse().is_ok_and(|n: usize| (1..=10).contains(&n)) {
+            validator.error(
+                layout.span().start,
+                format!("workspace {} does not exist (1 to 10)", number),
+            );
+        }
+        validator.check_layout(layout);
+    }
+    let primary = config.primary_modifier();
+    let mut buttons = HashSet::new();
+    for binding in config.mousebinding.iter() {
+        if !buttons.insert((binding.modifier_mask(primary), *binding.button.get_ref())) {
+            validator.error(
+                binding.button.span().start,
+                format!("button {} is bound twice", binding.button.get_ref()),
+            );
+        }
+    }
+    validator.diagnostics
+}
+
+struct Validator<'a> {
+    config: &'a Config,
+    content: &'a str,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Validator<'_> {
+    fn error(&mut self, offset: usize, message: String) {
+        self.diagnostics
+            .push(Diagnostic::at(self.content, offset, message));
+    }
+    // Checks one set of bindings active at the same time
+    fn check_keybindings(&mut self, bindings: &[Keybinding]) {
+        let primary = self.config.primary_modifier();
+        let mut combinations = HashSet::new();
+        for binding in bindings.iter() {
+            let key = &binding.key;
+            match X11Adapter::keysym_from_name(key.get_ref()) {
+                Some(keysym) => {
+                    if !combinations.insert((binding.modifier_mask(primary), keysym)) {
+                        self.error(
+                            key.span().start,
+                            format!("{} is bound twice", binding.describe()),
+                        );
+                    }
+                }
+                None => self.error(key.span().start, format!("unknown key {}", key.get_ref())),
+            }
+            match (&binding.action, binding.chord.is_empty()) {
+                (None, true) => self.error(
+                    key.span().start,
+                    format!("{} has no action nor chord", binding.describe()),
+                ),
+                (Some(action), false) => self.error(
+                    action.span().start,
+                    format!("{} has both an action and a chord", binding.describe()),
+                ),
+                (Some(action), true) => self.check_action(action),
+                (None, false) => self.check_keybindings(&binding.chord),
+            }
+        }
+    }
+    fn check_layout(&mut self, id: &Spanned<String>) {
+        if !LAYOUT_IDS.contains(&id.get_ref().as_str()) {
+            self.error(
+                id.span().start,
+                format!(
+                    "unknown layout {} (expected one of {})",
+                    id.get_ref(),
+                    LAYOUT_IDS.join(", ")
+                ),
+            );
+        }
+    }
+    fn check_action(&mut self, action: &Spanned<String>) {
+        let parsed = match action.get_ref().parse::<Action>() {
+            Ok(parsed) => parsed,
+            Err(e) => return self.error(action.span().start, e),
+        };
+        match parsed {
+            Action::Workspace(number)
+            | Action::MoveToWorkspace(number)
+            | Action::MoveToWorkspaceAndFollow(number)
+                if !(1..=10).contains(&number) =>
+            {
+                self.error(
+                    action.span().start,
+                    format!("workspace {} does not exist (1 to 10)", number),
+                )
+            }
+            Action::EnterMode(name)
+                if !self.config.mode.iter().any(|m| *m.name.get_ref() == name) =>
+            {
+                self.error(action.span().start, format!("unknown mode {}", name))
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn position() {
+        let content = "a = 1\nbb = 2\n\nccc";
+        assert_eq!(Diagnostic::at(content, 0, "".into()).position, Some((1, 1)));
+        assert_eq!(Diagnostic::at(content, 4, "".into()).position, Some((1, 5)));
+        assert_eq!(Diagnostic::at(content, 6, "".into()).position, Some((2, 1)));
+        assert_eq!(Diagnostic::at(content, 9, "".into()).position, Some((2, 4)));
+        assert_eq!(
+            Diagnostic::at(content, 14, "".into()).position,
+            Some((4, 1))
+        );
+        // past the end, points after the last character
+        assert_eq!(
+            Diagnostic::at(content, 99, "".into()).position,
+            Some((4, 4))
+        );
+    }
+
+    #[test]
+    fn toml_error_position() {
+        let content = "modifier = \"super\"\n\n[log]\nlevle = \"info\"\n";
+        let error = toml::from_str::<Config>(content).err().unwrap();
+        let diagnostic = Diagnostic::from_toml_error(error, content);
+        assert_eq!(diagnostic.position, Some((4, 1)));
+        assert!(diagnostic.message.starts_with("unknown field `levle`"));
+    }
+
+    #[test]
+    fn startup_unknown_field_position() {
+        let content =
+            "startup = [\n    \"picom\",\n    { command = \"polybar\", respwan = true },\n]\n\n\
+                       [log]\nlevel = \"info\"\n";
+        let error = toml::from_str::<Config>(content).err().unwrap();
+        let diagnostic = Diagnostic::from_toml_error(error, content);
+        assert_eq!(diagnostic.position, Some((3, 28)));
+        assert!(diagnostic.message.starts_with("unknown field `respwan`"));
+
+        let content = "[[startup]]\ncommand = \"feh\"\non_relaod = true\n";
+        let error = toml::from_str::<Config>(content).err().unwrap();
+        let diagnostic = Diagnostic::from_toml_error(error, content);
+        assert_eq!(diagnostic.position, Some((3, 1)));
+        assert!(diagnostic.message.starts_with("unknown field `on_relaod`"));
+    }
+
+    #[test]
+    fn every_error_is_reported() {
+        let content = "[[keybinding]]\nkey = \"a\"\naction = \"frobnicate\"\n\n\
+                       [[keybinding]]\nkey = \"b\"\naction = \"workspace 11\"\n\n\
+                       [layout]\ndefault = \"spiral\"\n";
+        let config: Config = toml::from_str(content).unwrap();
+        let positions: Vec<_> = validate(&config, content)
+            .iter()
+            .map(|d| d.position)
+            .collect();
+        assert_eq!(positions, [Some((3, 10)), Some((7, 10)), Some((10, 11))]);
+    }
+}
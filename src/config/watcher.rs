@@ -0,0 +1,75 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+// Checking the file on every X event would be wasteful
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Detects modifications of the config file by polling its mtime.
+pub struct ConfigWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    last_check: Instant,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: PathBuf) -> ConfigWatcher {
+        let modified = modification_time(&path);
+        ConfigWatcher {
+            path,
+            modified,
+            last_check: Instant::now(),
+        }
+    }
+    pub fn changed(&mut self) -> bool {
+        if self.last_check.elapsed() < CHECK_INTERVAL {
+            return false;
+        }
+        self.last_check = Instant::now();
+        self.check()
+    }
+    // A file which vanished is not a change: it may be replaced in a
+    // moment (editors saving by renaming) or the current config should
+    // be kept anyway. It is reloaded once it exists again.
+    fn check(&mut self) -> bool {
+        let Some(modified) = modification_time(&self.path) else {
+            return false;
+        };
+        if self.modified != Some(modified) {
+            self.modified = Some(modified);
+            return true;
+        }
+        false
+    }
+}
+
+fn modification_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs::File, process};
+
+    use super::*;
+
+    #[test]
+    fn missing_file_is_not_a_change() {
+        let path = env::temp_dir().join(format!("tdawm-watcher-{}.toml", process::id()));
+        fs::write(&path, "").unwrap();
+        let mut watcher = ConfigWatcher::new(path.clone());
+        assert!(!watcher.check());
+
+        fs::remove_file(&path).unwrap();
+        assert!(!watcher.check());
+
+        let file = File::create(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+        assert!(watcher.check());
+        assert!(!watcher.check());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,13 +1,19 @@
-use crate::tdawm::WindowId;
+use std::collections::HashMap;
+
+use crate::{
+    tdawm::{Screen, TDAWmError, Window, WindowId},
+    x11::X11Adapter,
+};
 
 use super::Layout;
 
-pub struct DWMLayout {
-    master: WindowId,
-}
+// The master area holds the first windows of the workspace stack,
+// the other ones share the rest of the screen
+#[derive(Debug)]
+pub struct DWMLayout;
 impl DWMLayout {
     pub fn init() -> Self {
-        DWMLayout { master: 0 }
+        DWMLayout {}
     }
 }
 impl Layout for DWMLayout {
@@ -15,48 +21,58 @@ impl Layout for DWMLayout {
         "dwm".into()
     }
 
-    fn set_master(&mut self, window: WindowId) {
-        self.master = window;
-    }
     fn layout(
-        &mut self,
-        server: &mut crate::x11::X11Adapter,
-        ctx: &mut crate::tdawm::Context,
-    ) -> Result<(), crate::tdawm::TDAWmError> {
-        for screen in ctx.screens.iter_mut() {
-            let ws = screen.current_workspace();
-            let length = ws.iter_normal_windows(&ctx.windows_by_id).count() as u32;
-            if length == 0 {
-                // not any windows
-                return Ok(());
-            }
-            if length == 1 {
-                //first and the only window
-                let window = ws.iter_normal_windows(&ctx.windows_by_id).next().unwrap();
-                server.move_window(window.id, screen.x as i32, screen.y as i32);
-                server.resize_window(window.id, screen.width, screen.height);
-                server.show_window(window.id);
-            } else {
-                let not_master_height = screen.height / (length - 1);
-                let width = screen.width / 2;
-                let mut i = 0;
-                for window in ws.iter_normal_windows(&ctx.windows_by_id) {
-                    if window.id != self.master {
-                        //not master
-                        let x = screen.x as i32 + width as i32;
-                        let y = screen.y as i32 + ((i as u32) * not_master_height) as i32;
-                        i += 1;
-                        server.move_window(window.id, x, y);
-                        server.resize_window(window.id, width, not_master_height);
-                    } else {
-                        //master
-                        server.move_window(window.id, screen.x as i32, screen.y as i32);
-                        server.resize_window(window.id, width, screen.height);
-                    }
-                    server.show_window(window.id);
-                }
-            }
+        &self,
+        server: &mut X11Adapter,
+        screen: &Screen,
+        windows: &HashMap<WindowId, Window>,
+    ) -> Result<(), TDAWmError> {
+        let ws = screen.current_workspace();
+        let tiled: Vec<WindowId> = ws.iter_normal_windows(windows).map(|w| w.id).collect();
+        if tiled.is_empty() {
+            // not any windows
+            return Ok(());
         }
+        let (masters, stack) = tiled.split_at(ws.nmaster.min(tiled.len()));
+        // a lone area takes the whole screen
+        let master_width = if stack.is_empty() {
+            screen.width
+        } else if masters.is_empty() {
+            0
+        } else {
+            (screen.width as f32 * ws.mfact) as u32
+        };
+        let x = screen.x as i32;
+        let y = screen.y as i32;
+        tile_column(server, masters, x, y, master_width, screen.height);
+        tile_column(
+            server,
+            stack,
+            x + master_width as i32,
+            y,
+            screen.width - master_width,
+            screen.height,
+        );
         Ok(())
     }
 }
+
+// Windows share the height of a column, from top to bottom
+fn tile_column(
+    server: &mut X11Adapter,
+    windows: &[WindowId],
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+) {
+    if windows.is_empty() {
+        return;
+    }
+    let window_height = height / windows.len() as u32;
+    for (i, window) in windows.iter().enumerate() {
+        server.move_window(*window, x, y + (i as u32 * window_height) as i32);
+        server.resize_window(*window, width, window_height);
+        server.show_window(*window);
+    }
+}
@@ -1,6 +1,7 @@
+use std::{collections::HashMap, fmt};
+
 use crate::{
-    tdawm::Context,
-    tdawm::{TDAWmError, WindowId},
+    tdawm::{Screen, TDAWmError, Window, WindowId},
     x11::X11Adapter,
 };
 
@@ -11,8 +12,25 @@ pub use vertical::*;
 mod dwm;
 pub use dwm::*;
 
-pub trait Layout {
+pub const LAYOUT_IDS: [&str; 3] = ["dwm", "horizontal", "vertical"];
+
+pub fn layout_from_id(id: &str) -> Option<Box<dyn Layout>> {
+    match id {
+        "dwm" => Some(Box::new(DWMLayout::init())),
+        "horizontal" => Some(Box::new(HorizontalLayout::init())),
+        "vertical" => Some(Box::new(VerticalLayout::init())),
+        _ => None,
+    }
+}
+
+/// Places the tiled windows of the current workspace of a screen,
+/// every workspace has its own layout.
+pub trait Layout: fmt::Debug {
     fn id(&self) -> String;
-    fn layout(&mut self, server: &mut X11Adapter, context: &mut Context) -> Result<(), TDAWmError>;
-    fn set_master(&mut self, _window: WindowId) {}
+    fn layout(
+        &self,
+        server: &mut X11Adapter,
+        screen: &Screen,
+        windows: &HashMap<WindowId, Window>,
+    ) -> Result<(), TDAWmError>;
 }
@@ -0,0 +1,203 @@
+// Logs go to a file kept across restarts (rotated by size) and
+// optionally to stdout. Settings can be changed while running so
+// a config reload applies them.
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard},
+};
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+/// Resolved logging settings, see `config::LogConfig`.
+#[derive(Debug, Clone)]
+pub struct LogSettings {
+    pub path: Option<PathBuf>,
+    pub level: LevelFilter,
+    // module path prefix (without the crate name) and its level
+    pub modules: Vec<(String, LevelFilter)>,
+    pub max_size: u64,
+    pub rotate: usize,
+    pub stdout: bool,
+}
+
+struct Output {
+    settings: LogSettings,
+    file: Option<File>,
+    size: u64,
+}
+
+struct Logger {
+    output: Mutex<Option<Output>>,
+}
+
+static LOGGER: Logger = Logger {
+    output: Mutex::new(None),
+};
+
+impl Logger {
+    // A panic while logging must not disable the logger for good
+    fn output(&self) -> MutexGuard<'_, Option<Output>> {
+        self.output.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Installs the logger, to be called once.
+pub fn init(settings: LogSettings) {
+    let _ = log::set_logger(&LOGGER);
+    configure(settings);
+}
+
+/// Applies new settings, the log file is reopened if it changed.
+pub fn configure(settings: LogSettings) {
+    let max_level = settings
+        .modules
+        .iter()
+        .map(|(_, level)| *level)
+        .fold(settings.level, Ord::max);
+    let mut output = LOGGER.output();
+    let reopen = output
+        .as_ref()
+        .is_none_or(|o| o.settings.path != settings.path);
+    let mut new_output = Output {
+        settings,
+        file: None,
+        size: 0,
+    };
+    match output.take() {
+        Some(old) if !reopen => {
+            new_output.file = old.file;
+            new_output.size = old.size;
+        }
+        _ => new_output.open(),
+    }
+    *output = Some(new_output);
+    log::set_max_level(max_level);
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let output = self.output();
+        output
+            .as_ref()
+            .is_some_and(|o| metadata.level() <= o.settings.level_for(metadata.target()))
+    }
+
+    fn log(&self, record: &Record) {
+        let mut output = self.output();
+        let Some(output) = output.as_mut() else {
+            return;
+        };
+        if record.level() > output.settings.level_for(record.target()) {
+            return;
+        }
+        let line = format!(
+            "[{}] [{}] {}: {}\n",
+            timestamp(),
+            record.level(),
+            module_name(record.target()),
+            record.args()
+        );
+        if output.settings.stdout {
+            // stdout may be closed, the file still gets the line
+            let _ = io::stdout().lock().write_all(line.as_bytes());
+        }
+        output.write(&line);
+    }
+
+    fn flush(&self) {
+        let mut output = self.output();
+        if let Some(file) = output.as_mut().and_then(|o| o.file.as_mut()) {
+            let _ = file.flush();
+        }
+    }
+}
+
+impl LogSettings {
+    // The most specific module filter wins
+    fn level_for(&self, target: &str) -> LevelFilter {
+        let module = module_name(target);
+        self.modules
+            .iter()
+            .filter(|(prefix, _)| module == prefix || module.starts_with(&format!("{}::", prefix)))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.level, |(_, level)| *level)
+    }
+}
+
+impl Output {
+    fn open(&mut self) {
+        self.file = None;
+        self.size = 0;
+        let Some(path) = self.settings.path.as_ref() else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        match OpenOptions::new().create(true).append(true).open(path) {
+            Ok(file) => {
+                self.size = file.metadata().map_or(0, |m| m.len());
+                self.file = Some(file);
+            }
+            Err(e) => eprintln!("unable to open log file {}: {}", path.display(), e),
+        }
+    }
+    fn write(&mut self, line: &str) {
+        let Some(file) = self.file.as_mut() else {
+            return;
+        };
+        if file.write_all(line.as_bytes()).is_err() {
+            return;
+        }
+        self.size += line.len() as u64;
+        if self.size >= self.settings.max_size {
+            self.rotate();
+        }
+    }
+    // tdawm.log -> tdawm.log.1 -> ... -> tdawm.log.<rotate>
+    fn rotate(&mut self) {
+        let Some(path) = self.settings.path.clone() else {
+            return;
+        };
+        if self.settings.rotate == 0 {
+            let _ = fs::remove_file(&path);
+        } else {
+            for i in (1..self.settings.rotate).rev() {
+                let _ = fs::rename(rotated(&path, i), rotated(&path, i + 1));
+            }
+            let _ = fs::rename(&path, rotated(&path, 1));
+        }
+        self.open();
+    }
+}
+
+fn rotated(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    name.into()
+}
+
+// tdawm::tdawm::tdawm -> tdawm::tdawm
+fn module_name(target: &str) -> &str {
+    target.strip_prefix("tdawm::").unwrap_or(target)
+}
+
+// Local time as 2024-01-31 12:34:56
+fn timestamp() -> String {
+    unsafe {
+        let now = libc::time(std::ptr::null_mut());
+        let mut tm: libc::tm = std::mem::zeroed();
+        libc::localtime_r(&now, &mut tm);
+        format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            tm.tm_year + 1900,
+            tm.tm_mon + 1,
+            tm.tm_mday,
+            tm.tm_hour,
+            tm.tm_min,
+            tm.tm_sec
+        )
+    }
+}
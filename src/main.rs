@@ -1,44 +1,131 @@
-use std::{error::Error, fs, path::Path};
+use std::{error::Error, path::PathBuf};
 
-use execute::{shell, Execute};
-use log::{error, info, Level};
+use log::{error, info};
 
 use crate::tdawm::TDAWm;
 
+mod autostart;
 mod config;
 mod layouts;
+mod logger;
+mod process;
+mod signals;
 mod tdawm;
 mod x11;
 fn main() {
-    let path = Path::new("/tmp/tdawm_log.txt");
-    if path.exists() {
-        fs::remove_file(path).unwrap();
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    if args.check_config {
+        std::process::exit(check_config(&args));
     }
-    let _log2 = log2::open(path.to_str().unwrap())
-        .module(true)
-        .tee(true)
-        .level(Level::Debug)
-        .rotate(5)
-        .start();
-    if let Err(e) = run() {
+    if let Some(command) = args.message {
+        std::process::exit(send_message(&command));
+    }
+    // Settings from the config are applied once it is loaded
+    logger::init(config::LogConfig::default().settings());
+    if let Err(e) = run(args) {
         error!("{}", e);
     }
 }
 
-fn run() -> Result<(), Box<dyn Error>> {
+#[derive(Default)]
+struct Args {
+    config: Option<PathBuf>,
+    check_config: bool,
+    // tdawm msg <action>
+    message: Option<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args::default();
+    let mut iter = std::env::args().skip(1).peekable();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--config" => {
+                let path = iter.next().ok_or("--config needs a path")?;
+                args.config = Some(path.into());
+            }
+            // --check-config [path]
+            "--check-config" => {
+                args.check_config = true;
+                if let Some(path) = iter.next_if(|a| !a.starts_with("--")) {
+                    args.config = Some(path.into());
+                }
+            }
+            "msg" => {
+                let command: Vec<String> = iter.by_ref().collect();
+                if command.is_empty() {
+                    return Err("msg needs an action".into());
+                }
+                args.message = Some(command.join(" "));
+            }
+            _ => return Err(format!("unknown argument {}", arg)),
+        }
+    }
+    Ok(args)
+}
+
+// Prints every problem found in the config, returns the exit code.
+fn check_config(args: &Args) -> i32 {
+    let diagnostics = config::find_config(args.config.as_deref()).and_then(|path| {
+        let name = path
+            .as_ref()
+            .map_or("built-in config".into(), |p| p.display().to_string());
+        config::check_config(path.as_deref()).map(|d| (name, d))
+    });
+    match diagnostics {
+        Ok((name, diagnostics)) if diagnostics.is_empty() => {
+            println!("{}: ok", name);
+            0
+        }
+        Ok((name, diagnostics)) => {
+            for diagnostic in diagnostics.iter() {
+                eprintln!("{}:{}", name, diagnostic);
+            }
+            1
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+// Sends an action to the running tdawm, returns the exit code.
+fn send_message(command: &str) -> i32 {
+    if let Err(e) = command.parse::<tdawm::Action>() {
+        eprintln!("invalid action: {}", e);
+        return 1;
+    }
+    let adapter = std::env::var("DISPLAY")
+        .map_err(|e| e.to_string())
+        .and_then(|display| x11::X11Adapter::new(&display).map_err(|e| e.to_string()));
+    match adapter {
+        Ok(mut adapter) => {
+            adapter.send_command(command);
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+fn run(args: Args) -> Result<(), Box<dyn Error>> {
     let display_name = std::env::var("DISPLAY")?;
-    let user_config: config::Config = config::load_config()?;
+    let config_path = config::find_config(args.config.as_deref())?;
+    let user_config: config::Config = config::load_config(config_path.as_deref())?;
+    logger::configure(user_config.log.settings());
     let adapter = x11::X11Adapter::new(&display_name)?;
-    let mut wm = TDAWm::new(adapter)?;
-    info!("running startup");
-    for cmd in user_config.startup.iter() {
-        info!("executing {}", cmd);
-        let mut command = shell(cmd);
-        command.execute()?;
-    }
-    // if let Ok(_) = env::var("XEPHYR") {
-    //     // wm.set_modifier_to_control();
-    // }
+    info!("using {:?} as modifier", user_config.primary_modifier());
+    signals::install();
+    let mut wm = TDAWm::new(adapter, user_config, args.config)?;
     wm.run()?;
     Ok(())
 }
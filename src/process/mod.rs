@@ -0,0 +1,104 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io,
+    os::unix::process::{CommandExt, ExitStatusExt},
+    process::{ExitStatus, Stdio},
+    time::Instant,
+};
+
+use execute::shell;
+use log::{debug, info, warn};
+
+pub type Pid = u32;
+
+/// A child which has been reaped.
+#[derive(Debug, Clone, Copy)]
+pub struct Exited {
+    pub pid: Pid,
+    pub status: ExitStatus,
+}
+
+#[derive(Debug)]
+pub struct ChildProcess {
+    pub command: String,
+    pub started_at: Instant,
+}
+
+/// Every program started by tdawm. Children are detached in their
+/// own session and reaped with waitpid when SIGCHLD is received,
+/// so `std::process::Child::wait` must never be used on them.
+#[derive(Default)]
+pub struct Processes {
+    children: HashMap<Pid, ChildProcess>,
+}
+
+impl Processes {
+    /// Runs a command through the shell.
+    pub fn spawn(&mut self, command: &str) -> io::Result<Pid> {
+        self.spawn_with_output(command, None)
+    }
+    /// Runs a command through the shell, its stdout and stderr going to `output`.
+    pub fn spawn_with_output(&mut self, command: &str, output: Option<File>) -> io::Result<Pid> {
+        let mut process = shell(command);
+        process.stdin(Stdio::null());
+        if let Some(output) = output {
+            process.stderr(output.try_clone()?);
+            process.stdout(output);
+        }
+        // Not in our session, a signal sent to tdawm's process group
+        // (Ctrl+C in the terminal which started X) won't kill them.
+        unsafe {
+            process.pre_exec(|| {
+                libc::setsid();
+                Ok(())
+            });
+        }
+        let pid = process.spawn()?.id();
+        info!("started {} with pid {}", command, pid);
+        self.children.insert(
+            pid,
+            ChildProcess {
+                command: command.to_string(),
+                started_at: Instant::now(),
+            },
+        );
+        Ok(pid)
+    }
+    /// Collects every child which exited since the last call.
+    pub fn reap(&mut self) -> Vec<Exited> {
+        let mut exited = vec![];
+        loop {
+            let mut status = 0;
+            let pid = unsafe { libc::waitpid(-1, &mut status, libc::WNOHANG) };
+            if pid <= 0 {
+                break;
+            }
+            let exit = Exited {
+                pid: pid as Pid,
+                status: ExitStatus::from_raw(status),
+            };
+            match self.children.remove(&exit.pid) {
+                // commands go through the shell, a command which can't
+                // be started is only seen here (exit status 127)
+                Some(child) if !exit.status.success() => warn!(
+                    "{} ({}) exited with {} after {:?}",
+                    child.command,
+                    pid,
+                    exit.status,
+                    child.started_at.elapsed()
+                ),
+                Some(child) => debug!(
+                    "{} ({}) exited with {} after {:?}",
+                    child.command,
+                    pid,
+                    exit.status,
+                    child.started_at.elapsed()
+                ),
+                None => debug!("unknown child {} exited with {}", pid, exit.status),
+            }
+            exited.push(exit);
+        }
+        exited
+    }
+}
@@ -0,0 +1,34 @@
+// Signal handlers only set flags, the main loop checks them
+// between two X events.
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use log::info;
+
+static RELOAD_REQUESTED: AtomicBool = AtomicBool::new(false);
+static CHILD_EXITED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_sighup(_: libc::c_int) {
+    RELOAD_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+extern "C" fn on_sigchld(_: libc::c_int) {
+    CHILD_EXITED.store(true, Ordering::SeqCst);
+}
+
+pub fn install() {
+    info!("installing signal handlers");
+    unsafe {
+        libc::signal(libc::SIGHUP, on_sighup as *const () as libc::sighandler_t);
+        libc::signal(libc::SIGCHLD, on_sigchld as *const () as libc::sighandler_t);
+    }
+}
+
+/// True once after SIGHUP has been received.
+pub fn take_reload_request() -> bool {
+    RELOAD_REQUESTED.swap(false, Ordering::SeqCst)
+}
+
+/// True once after SIGCHLD has been received.
+pub fn take_child_exited() -> bool {
+    CHILD_EXITED.swap(false, Ordering::SeqCst)
+}
@@ -0,0 +1,308 @@
+use std::{fmt, str::FromStr};
+
+use crate::layouts::LAYOUT_IDS;
+
+use super::Direction;
+
+/// Something the window manager can be asked to do, by a keybinding
+/// or by another program through `tdawm msg`.
+/// Actions are written as a name followed by an optional argument,
+/// for example "workspace 3" or "spawn alacritty".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Switch between the horizontal and vertical layouts.
+    ToggleLayout,
+    /// Use the layout with this id on the current workspace.
+    SetLayout(String),
+    /// Use the next layout of the configured cycle on the current workspace.
+    NextLayout,
+    /// Use the previous layout of the configured cycle on the current workspace.
+    PrevLayout,
+    /// Run a command through the shell.
+    Spawn(String),
+    /// Go to the workspace with the given number (starting at 1).
+    Workspace(usize),
+    /// Go back to the workspace shown before the current one.
+    PreviousWorkspace,
+    /// Send the focused window to the workspace with the given number.
+    MoveToWorkspace(usize),
+    /// Send the focused window to the workspace with the given number
+    /// and go there with it.
+    MoveToWorkspaceAndFollow(usize),
+    /// Focus the window after the focused one, in layout order.
+    FocusNext,
+    /// Focus the window before the focused one, in layout order.
+    FocusPrev,
+    /// Focus the master window of the current workspace.
+    FocusMaster,
+    /// Focus the closest window in this direction, on this screen
+    /// or the next one.
+    FocusDirection(Direction),
+    /// Swap the focused window with the closest tiled window in this direction.
+    SwapDirection(Direction),
+    /// Swap the focused window with the next one in the stack.
+    SwapNext,
+    /// Swap the focused window with the previous one in the stack.
+    SwapPrev,
+    /// Make the focused window the master, or swap the master
+    /// with the next window when it is already focused.
+    Zoom,
+    /// Move every tiled window one place down the stack.
+    RotateNext,
+    /// Move every tiled window one place up the stack.
+    RotatePrev,
+    /// Put one more window in the master area.
+    IncNmaster,
+    /// Put one less window in the master area.
+    DecNmaster,
+    /// Make the master area wider.
+    IncMfact,
+    /// Make the master area narrower.
+    DecMfact,
+    /// Ask the focused window to close.
+    Close,
+    /// Take the focused window out of the layout, or put it back.
+    ToggleFloating,
+    /// Make the focused window cover its screen, or give it back its place.
+    ToggleFullscreen,
+    /// Activate the binding mode with this name.
+    EnterMode(String),
+    /// Leave the current binding mode.
+    ExitMode,
+    /// Read the config file again.
+    Reload,
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (name, argument) = match s.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (s, ""),
+        };
+        let action = match name {
+            "toggle-layout" => Action::ToggleLayout,
+            "set-layout" => {
+                let id = required(name, argument)?;
+                if !LAYOUT_IDS.contains(&id) {
+                    return Err(format!(
+                        "unknown layout {} (expected one of {})",
+                        id,
+                        LAYOUT_IDS.join(", ")
+                    ));
+                }
+                Action::SetLayout(id.into())
+            }
+            "next-layout" => Action::NextLayout,
+            "prev-layout" => Action::PrevLayout,
+            "spawn" => Action::Spawn(required(name, argument)?.into()),
+            "workspace" => Action::Workspace(number(name, argument)?),
+            "previous-workspace" => Action::PreviousWorkspace,
+            "move-to-workspace" => Action::MoveToWorkspace(number(name, argument)?),
+            "move-to-workspace-and-follow" => {
+                Action::MoveToWorkspaceAndFollow(number(name, argument)?)
+            }
+            "focus-next" => Action::FocusNext,
+            "focus-prev" => Action::FocusPrev,
+            "focus-master" => Action::FocusMaster,
+            "swap-next" => Action::SwapNext,
+            "swap-prev" => Action::SwapPrev,
+            "zoom" => Action::Zoom,
+            "rotate-next" => Action::RotateNext,
+            "rotate-prev" => Action::RotatePrev,
+            "inc-nmaster" => Action::IncNmaster,
+            "dec-nmaster" => Action::DecNmaster,
+            "inc-mfact" => Action::IncMfact,
+            "dec-mfact" => Action::DecMfact,
+            "close" => Action::Close,
+            "toggle-floating" => Action::ToggleFloating,
+            "toggle-fullscreen" => Action::ToggleFullscreen,
+            "enter-mode" => Action::EnterMode(required(name, argument)?.into()),
+            "exit-mode" => Action::ExitMode,
+            "reload" => Action::Reload,
+            "" => return Err("empty action".into()),
+            _ => match (directional(name, "focus-"), directional(name, "swap-")) {
+                (Some(direction), _) => Action::FocusDirection(direction),
+                (_, Some(direction)) => Action::SwapDirection(direction),
+                _ => return Err(format!("unknown action {}", name)),
+            },
+        };
+        if !action.takes_argument() && !argument.is_empty() {
+            return Err(format!("{} takes no argument", name));
+        }
+        Ok(action)
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::ToggleLayout => write!(f, "toggle-layout"),
+            Action::SetLayout(id) => write!(f, "set-layout {}", id),
+            Action::NextLayout => write!(f, "next-layout"),
+            Action::PrevLayout => write!(f, "prev-layout"),
+            Action::Spawn(command) => write!(f, "spawn {}", command),
+            Action::Workspace(number) => write!(f, "workspace {}", number),
+            Action::PreviousWorkspace => write!(f, "previous-workspace"),
+            Action::MoveToWorkspace(number) => write!(f, "move-to-workspace {}", number),
+            Action::MoveToWorkspaceAndFollow(number) => {
+                write!(f, "move-to-workspace-and-follow {}", number)
+            }
+            Action::FocusNext => write!(f, "focus-next"),
+            Action::FocusPrev => write!(f, "focus-prev"),
+            Action::FocusMaster => write!(f, "focus-master"),
+            Action::FocusDirection(direction) => write!(f, "focus-{}", direction.name()),
+            Action::SwapDirection(direction) => write!(f, "swap-{}", direction.name()),
+            Action::SwapNext => write!(f, "swap-next"),
+            Action::SwapPrev => write!(f, "swap-prev"),
+            Action::Zoom => write!(f, "zoom"),
+            Action::RotateNext => write!(f, "rotate-next"),
+            Action::RotatePrev => write!(f, "rotate-prev"),
+            Action::IncNmaster => write!(f, "inc-nmaster"),
+            Action::DecNmaster => write!(f, "dec-nmaster"),
+            Action::IncMfact => write!(f, "inc-mfact"),
+            Action::DecMfact => write!(f, "dec-mfact"),
+            Action::Close => write!(f, "close"),
+            Action::ToggleFloating => write!(f, "toggle-floating"),
+            Action::ToggleFullscreen => write!(f, "toggle-fullscreen"),
+            Action::EnterMode(name) => write!(f, "enter-mode {}", name),
+            Action::ExitMode => write!(f, "exit-mode"),
+            Action::Reload => write!(f, "reload"),
+        }
+    }
+}
+
+impl Action {
+    fn takes_argument(&self) -> bool {
+        matches!(
+            self,
+            Action::SetLayout(_)
+                | Action::Spawn(_)
+                | Action::Workspace(_)
+                | Action::MoveToWorkspace(_)
+                | Action::MoveToWorkspaceAndFollow(_)
+                | Action::EnterMode(_)
+        )
+    }
+}
+
+// focus-left, swap-down, ...
+fn directional(name: &str, prefix: &str) -> Option<Direction> {
+    Direction::from_name(name.strip_prefix(prefix)?)
+}
+
+fn required<'a>(name: &str, argument: &'a str) -> Result<&'a str, String> {
+    if argument.is_empty() {
+        return Err(format!("{} needs an argument", name));
+    }
+    Ok(argument)
+}
+
+fn number(name: &str, argument: &str) -> Result<usize, String> {
+    required(name, argument)?
+        .parse()
+        .map_err(|_| format!("{} needs a number, got {}", name, argument))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let actions = [
+            "toggle-layout",
+            "set-layout vertical",
+            "next-layout",
+            "prev-layout",
+            "spawn alacritty -e htop",
+            "workspace 3",
+            "previous-workspace",
+            "move-to-workspace 10",
+            "move-to-workspace-and-follow 1",
+            "focus-next",
+            "focus-prev",
+            "focus-master",
+            "focus-left",
+            "focus-right",
+            "focus-up",
+            "focus-down",
+            "swap-left",
+            "swap-right",
+            "swap-up",
+            "swap-down",
+            "swap-next",
+            "swap-prev",
+            "zoom",
+            "rotate-next",
+            "rotate-prev",
+            "inc-nmaster",
+            "dec-nmaster",
+            "inc-mfact",
+            "dec-mfact",
+            "close",
+            "toggle-floating",
+            "toggle-fullscreen",
+            "enter-mode resize",
+            "exit-mode",
+            "reload",
+        ];
+        for text in actions {
+            let action: Action = text.parse().unwrap();
+            assert_eq!(action.to_string(), text);
+        }
+    }
+
+    #[test]
+    fn whitespace() {
+        assert_eq!("  workspace   4 ".parse(), Ok(Action::Workspace(4)));
+        assert_eq!(
+            "spawn  st -e  vim".parse(),
+            Ok(Action::Spawn("st -e  vim".into()))
+        );
+    }
+
+    #[test]
+    fn directions() {
+        assert_eq!(
+            "focus-left".parse(),
+            Ok(Action::FocusDirection(Direction::Left))
+        );
+        assert_eq!(
+            "swap-down".parse(),
+            Ok(Action::SwapDirection(Direction::Down))
+        );
+        assert_eq!(
+            "focus-middle".parse::<Action>(),
+            Err("unknown action focus-middle".into())
+        );
+        assert_eq!(
+            "swap-".parse::<Action>(),
+            Err("unknown action swap-".into())
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let error = |text: &str| text.parse::<Action>().unwrap_err();
+        assert_eq!(error(""), "empty action");
+        assert_eq!(error("frobnicate"), "unknown action frobnicate");
+        assert_eq!(error("spawn"), "spawn needs an argument");
+        assert_eq!(error("enter-mode "), "enter-mode needs an argument");
+        assert_eq!(error("workspace two"), "workspace needs a number, got two");
+        assert_eq!(
+            error("move-to-workspace -1"),
+            "move-to-workspace needs a number, got -1"
+        );
+        assert_eq!(error("close now"), "close takes no argument");
+        assert_eq!(
+            error("set-layout spiral"),
+            format!(
+                "unknown layout spiral (expected one of {})",
+                LAYOUT_IDS.join(", ")
+            )
+        );
+    }
+}
@@ -19,8 +19,7 @@ impl Context {
     }
     fn focused_screen_index(&self, mouse_position: (i16, i16)) -> usize {
         let (mouse_x, mouse_y) = mouse_position;
-        return self
-            .screens
+        self.screens
             .iter()
             .enumerate()
             .find(|(_, screen)| {
@@ -30,6 +29,6 @@ impl Context {
                     && screen.y < mouse_y
                     && screen.y + screen.height as i16 > mouse_y
             })
-            .map_or(0, |(i, _)| i);
+            .map_or(0, |(i, _)| i)
     }
 }
@@ -0,0 +1,62 @@
+use super::{Position, Size};
+
+/// A direction on the screens, used to find the neighbor of a window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl Direction {
+    pub fn from_name(name: &str) -> Option<Direction> {
+        match name {
+            "left" => Some(Direction::Left),
+            "right" => Some(Direction::Right),
+            "up" => Some(Direction::Up),
+            "down" => Some(Direction::Down),
+            _ => None,
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            Direction::Left => "left",
+            Direction::Right => "right",
+            Direction::Up => "up",
+            Direction::Down => "down",
+        }
+    }
+    /// How far `to` is from `from` in this direction, None when it is not
+    /// that way. A window is only that way when it faces `from`: it must be
+    /// level with it (left, right) or above or below it (up, down).
+    pub fn distance(&self, from: (Position, Size), to: (Position, Size)) -> Option<i32> {
+        let (from_x, from_y) = center(from);
+        let (to_x, to_y) = center(to);
+        let (along, across, facing) = match self {
+            Direction::Left => (from_x - to_x, to_y - from_y, overlap_y(from, to)),
+            Direction::Right => (to_x - from_x, to_y - from_y, overlap_y(from, to)),
+            Direction::Up => (from_y - to_y, to_x - from_x, overlap_x(from, to)),
+            Direction::Down => (to_y - from_y, to_x - from_x, overlap_x(from, to)),
+        };
+        if along <= 0 || !facing {
+            return None;
+        }
+        Some(along + across.abs())
+    }
+}
+
+fn center((position, size): (Position, Size)) -> (i32, i32) {
+    (
+        position.x + size.x as i32 / 2,
+        position.y + size.y as i32 / 2,
+    )
+}
+
+fn overlap_x(a: (Position, Size), b: (Position, Size)) -> bool {
+    a.0.x < b.0.x + b.1.x as i32 && b.0.x < a.0.x + a.1.x as i32
+}
+
+fn overlap_y(a: (Position, Size), b: (Position, Size)) -> bool {
+    a.0.y < b.0.y + b.1.y as i32 && b.0.y < a.0.y + a.1.y as i32
+}
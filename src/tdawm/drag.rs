@@ -0,0 +1,30 @@
+use crate::config::MouseAction;
+
+use super::{Position, Size, WindowId};
+
+/// A window being moved or resized with the mouse.
+#[derive(Debug, Clone, Copy)]
+pub struct Drag {
+    pub window: WindowId,
+    pub action: MouseAction,
+    // pointer position when the button was pressed
+    pub pointer: Position,
+    // window geometry when the button was pressed
+    pub position: Position,
+    pub size: Size,
+}
+
+impl Drag {
+    pub fn moved_position(&self, pointer: Position) -> Position {
+        Position {
+            x: self.position.x + pointer.x - self.pointer.x,
+            y: self.position.y + pointer.y - self.pointer.y,
+        }
+    }
+    pub fn resized_size(&self, pointer: Position) -> Size {
+        Size {
+            x: (self.size.x as i32 + pointer.x - self.pointer.x).max(1) as u32,
+            y: (self.size.y as i32 + pointer.y - self.pointer.y).max(1) as u32,
+        }
+    }
+}
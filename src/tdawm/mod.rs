@@ -1,3 +1,4 @@
+#[allow(clippy::module_inception)]
 mod tdawm;
 pub use tdawm::*;
 mod workspace;
@@ -8,3 +9,11 @@ mod screen;
 pub use screen::*;
 mod context;
 pub use context::*;
+mod action;
+pub use action::*;
+mod mode;
+pub use mode::*;
+mod drag;
+pub use drag::*;
+mod direction;
+pub use direction::*;
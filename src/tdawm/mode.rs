@@ -0,0 +1,41 @@
+use crate::config::Keybinding;
+
+/// A set of bindings replacing the default ones while it is active.
+#[derive(Debug, Clone)]
+pub struct Mode {
+    pub name: String,
+    pub bindings: Vec<Keybinding>,
+    // Chords only last for one key press
+    pub oneshot: bool,
+}
+
+/// Active binding modes, the default mode (configured keybindings)
+/// is used when the stack is empty.
+#[derive(Debug, Default)]
+pub struct ModeStack {
+    modes: Vec<Mode>,
+}
+
+impl ModeStack {
+    pub fn push(&mut self, mode: Mode) {
+        self.modes.push(mode);
+    }
+    pub fn pop(&mut self) -> Option<Mode> {
+        self.modes.pop()
+    }
+    pub fn clear(&mut self) {
+        self.modes.clear();
+    }
+    pub fn is_default(&self) -> bool {
+        self.modes.is_empty()
+    }
+    pub fn in_chord(&self) -> bool {
+        self.modes.last().is_some_and(|m| m.oneshot)
+    }
+    pub fn name(&self) -> &str {
+        self.modes.last().map_or("default", |m| m.name.as_str())
+    }
+    pub fn bindings<'a>(&'a self, default: &'a [Keybinding]) -> &'a [Keybinding] {
+        self.modes.last().map_or(default, |m| m.bindings.as_slice())
+    }
+}
@@ -8,6 +8,8 @@ pub struct Screen {
     pub y: i16,
     pub workspaces: Vec<Workspace>,
     pub current_workspace_id: usize,
+    // workspaces visited before the current one, the most recent last
+    pub workspace_history: Vec<usize>,
     pub focused_window: WindowId,
 }
 
@@ -20,12 +22,10 @@ impl Screen {
             y,
             workspaces: std::iter::repeat_with(Workspace::new).take(10).collect(),
             current_workspace_id: 0,
+            workspace_history: Vec::new(),
             focused_window: 0,
         }
     }
-    pub fn window_workspace(&self, id: WindowId) -> Option<&Workspace> {
-        self.workspaces.iter().find(|w| w.windows.contains(&id))
-    }
     pub fn has_window_visible(&self, id: WindowId) -> bool {
         if self
             .current_workspace()
@@ -39,6 +39,21 @@ impl Screen {
         }
         false
     }
+    /// Shows another workspace, the current one goes on top of the history.
+    pub fn set_current_workspace(&mut self, index: usize) {
+        if index == self.current_workspace_id || index >= self.workspaces.len() {
+            return;
+        }
+        let current = self.current_workspace_id;
+        self.workspace_history
+            .retain(|w| *w != current && *w != index);
+        self.workspace_history.push(current);
+        self.current_workspace_id = index;
+    }
+    /// The workspace shown before the current one.
+    pub fn previous_workspace(&self) -> Option<usize> {
+        self.workspace_history.last().copied()
+    }
     pub fn current_workspace_mut(&mut self) -> &mut Workspace {
         self.workspaces.get_mut(self.current_workspace_id).unwrap()
     }
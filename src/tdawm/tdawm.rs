@@ -2,21 +2,32 @@
 // EWMH are some hints for status bar for example.
 // https://en.wikipedia.org/wiki/Extended_Window_Manager_Hints
 
+use super::Action;
 use super::Context;
+use super::Direction;
+use super::Drag;
+use super::Mode;
+use super::ModeStack;
 use super::Position;
+use super::Screen;
 use super::Size;
 use super::Window;
 use super::WindowId;
-use super::Workspace;
+use crate::autostart::Autostart;
+use crate::config::{self, Config, ConfigWatcher, DragTiled, Mfact, MouseAction};
 use crate::layouts::*;
+use crate::logger;
+use crate::process::Processes;
+use crate::signals;
 use crate::tdawm::WindowType;
 use crate::x11;
+use ::x11::keysym;
 use ::x11::xlib;
-use log::error;
 use log::trace;
-use log::{debug, info};
+use log::{debug, error, info, warn};
 use std::collections::HashMap;
-use std::process::Command;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 #[derive(Error, Debug)]
 pub enum TDAWmError {
@@ -24,36 +35,166 @@ pub enum TDAWmError {
     NoScreenFound,
 }
 
+// How much inc-mfact and dec-mfact change the master area
+const MFACT_STEP: f32 = 0.05;
+
+// How long the main loop waits for an X event
+// before checking signals and the config file.
+const EVENT_TIMEOUT: Duration = Duration::from_secs(1);
+
 pub type Keycode = i32;
+pub type Keysym = u64;
 
 pub struct TDAWm {
     pub server: x11::X11Adapter,
-    current_layout: Box<dyn Layout>,
     ctx: Context,
+    config: Config,
+    modes: ModeStack,
+    drag: Option<Drag>,
+    config_watcher: Option<ConfigWatcher>,
+    // --config given on the command line
+    config_override: Option<PathBuf>,
+    autostart: Autostart,
+    processes: Processes,
+    // Windows asked to close, with the time they get killed at
+    closing: HashMap<WindowId, Instant>,
 }
 impl TDAWm {
-    pub fn new(mut server: x11::X11Adapter) -> Result<TDAWm, TDAWmError> {
+    pub fn new(
+        mut server: x11::X11Adapter,
+        config: Config,
+        config_override: Option<PathBuf>,
+    ) -> Result<TDAWm, TDAWmError> {
         let screens = server.init();
-        server.grab_key(xlib::AnyKey, xlib::ControlMask);
-        let mut workspaces = Vec::new();
-        for _ in 0..10 {
-            workspaces.push(Workspace::new());
+        if screens.is_empty() {
+            return Err(TDAWmError::NoScreenFound);
         }
         let context = Context {
             screens,
             windows_by_id: HashMap::new(),
         };
-        let t = TDAWm {
+        let mut t = TDAWm {
             server,
             ctx: context,
-            current_layout: Box::new(crate::layouts::DWMLayout::init()),
+            config,
+            modes: ModeStack::default(),
+            drag: None,
+            config_watcher: None,
+            config_override,
+            autostart: Autostart::default(),
+            processes: Processes::default(),
+            closing: HashMap::new(),
         };
+        t.apply_layout_config();
+        t.grab_keys();
+        t.grab_buttons();
+        t.watch_config();
         Ok(t)
     }
+    fn watch_config(&mut self) {
+        self.config_watcher = None;
+        if self.config.watch_config {
+            match config::find_config(self.config_override.as_deref()) {
+                Ok(Some(path)) => self.config_watcher = Some(ConfigWatcher::new(path)),
+                Ok(None) => warn!("no config file to watch"),
+                Err(e) => error!("unable to watch config: {}", e),
+            }
+        }
+    }
+    // Windows and workspaces are kept as they are, only the
+    // bindings and the layout are applied again.
+    // An invalid or missing config is ignored.
+    fn reload_config(&mut self) -> Result<(), TDAWmError> {
+        info!("reloading config");
+        let config = match config::find_config(self.config_override.as_deref())
+            .and_then(|path| config::load_valid_config(path.as_deref()))
+        {
+            Ok(config) => config,
+            Err(e) => {
+                error!("keeping current config: {}", e);
+                return Ok(());
+            }
+        };
+        // runtime changes are kept unless the defaults changed
+        let layout_changed = config.layout != self.config.layout;
+        self.config = config;
+        logger::configure(self.config.log.settings());
+        if layout_changed {
+            self.apply_layout_config();
+        }
+        if !self.modes.is_default() {
+            self.modes.clear();
+            self.server.ungrab_keyboard();
+            self.server.set_mode_property(self.modes.name());
+        }
+        self.grab_keys();
+        self.grab_buttons();
+        self.watch_config();
+        self.autostart
+            .reload(&self.config.startup, &mut self.processes);
+        self.layout()
+    }
+    fn apply_layout_config(&mut self) {
+        for screen in self.ctx.screens.iter_mut() {
+            for (index, workspace) in screen.workspaces.iter_mut().enumerate() {
+                workspace.apply_layout_config(&self.config.layout, index + 1);
+            }
+        }
+    }
+    // Only the configured combinations are grabbed, every other
+    // key press keeps going to the focused application.
+    // Keysyms are resolved to keycodes each time, so this has to
+    // be called again when the keyboard mapping changes.
+    fn grab_keys(&self) {
+        self.server.ungrab_key(xlib::AnyKey, xlib::AnyModifier);
+        let primary = self.config.primary_modifier();
+        for binding in self.config.keybinding.iter() {
+            let Some(keysym) = x11::X11Adapter::keysym_from_name(binding.key.get_ref()) else {
+                error!("unknown key {} in keybinding", binding.key.get_ref());
+                continue;
+            };
+            let Some(keycode) = self.server.keycode_from_keysym(keysym) else {
+                warn!(
+                    "key {} is not on the current keyboard",
+                    binding.key.get_ref()
+                );
+                continue;
+            };
+            self.server
+                .grab_key(keycode, binding.modifier_mask(primary));
+        }
+    }
+    fn grab_buttons(&self) {
+        self.server.ungrab_buttons();
+        let primary = self.config.primary_modifier();
+        for binding in self.config.mousebinding.iter() {
+            self.server
+                .grab_button(*binding.button.get_ref(), binding.modifier_mask(primary));
+        }
+    }
     pub fn run(&mut self) -> Result<(), TDAWmError> {
         self.server.ewmh_set_current_desktop(0);
+        self.server.set_mode_property(self.modes.name());
+        self.autostart
+            .start(&self.config.startup, &mut self.processes);
         loop {
-            let event = self.server.next_event();
+            let exited = if signals::take_child_exited() {
+                self.processes.reap()
+            } else {
+                vec![]
+            };
+            self.autostart.supervise(&exited, &mut self.processes);
+            self.kill_unresponsive();
+            let config_changed = self
+                .config_watcher
+                .as_mut()
+                .is_some_and(|watcher| watcher.changed());
+            if signals::take_reload_request() || config_changed {
+                self.reload_config()?;
+            }
+            let Some(event) = self.server.next_event(EVENT_TIMEOUT) else {
+                continue;
+            };
             match event.get_type() {
                 xlib::CreateNotify => {
                     let event: xlib::XCreateWindowEvent = From::from(event);
@@ -72,20 +213,38 @@ impl TDAWm {
                 xlib::KeyPress => {
                     self.handle_keypress(event)?;
                 }
+                xlib::ButtonPress => {
+                    self.handle_button_press(event)?;
+                }
+                xlib::MotionNotify => {
+                    self.handle_motion(event);
+                }
+                xlib::ButtonRelease => {
+                    self.handle_button_release(event)?;
+                }
+                // Keyboard layout changed (setxkbmap, xmodmap, ...)
+                xlib::MappingNotify => {
+                    let mut event: xlib::XMappingEvent = From::from(event);
+                    self.server.refresh_keyboard_mapping(&mut event);
+                    if event.request == xlib::MappingKeyboard
+                        || event.request == xlib::MappingModifier
+                    {
+                        info!("keyboard mapping changed, grabbing keys again");
+                        self.grab_keys();
+                        self.grab_buttons();
+                    }
+                }
 
                 // When cursor enters a window
                 xlib::EnterNotify => {
                     let event: xlib::XEnterWindowEvent = From::from(event);
-                    if let Some((i, screen)) = self
+                    if self
                         .ctx
                         .screens
-                        .iter_mut()
-                        .enumerate()
-                        .find(|(i, screen)| screen.has_window_visible(event.window))
+                        .iter()
+                        .any(|screen| screen.has_window_visible(event.window))
                     {
-                        screen.focused_window = event.window;
-                        self.server.focus_window(event.window);
-                        // self.ctx.focused_screen = i;
+                        self.focus(event.window);
                     }
                 }
                 xlib::ClientMessage => {
@@ -97,10 +256,15 @@ impl TDAWm {
                             .identify(event.message_type, self.server.display),
                         event.window
                     );
+                    self.handle_client_message(event)?;
                 }
                 xlib::PropertyNotify => {
                     let event: xlib::XPropertyEvent = From::from(event);
-                    self.load_window_properties(event.window);
+                    if event.window == self.server.root_window {
+                        self.handle_root_property(event)?;
+                    } else {
+                        self.load_window_properties(event.window);
+                    }
                 }
                 xlib::ConfigureRequest => {
                     debug!("received configure request event {:?}", event);
@@ -132,11 +296,16 @@ impl TDAWm {
         info!("registering new window with id {}", event.window);
 
         self.server.put_window_on_top(event.window as WindowId);
-        self.server.focus_window(event.window as WindowId);
-        self.ctx
-            .focused_screen_mut(self.server.get_mouse_position())
-            .current_workspace_mut()
-            .add_window(event.window as WindowId);
+        let screen = self
+            .ctx
+            .focused_screen_mut(self.server.get_mouse_position());
+        let focused = screen.focused_window;
+        screen.current_workspace_mut().insert_window(
+            event.window as WindowId,
+            self.config.new_window,
+            focused,
+        );
+        self.focus(event.window as WindowId);
 
         self.ctx
             .windows_by_id
@@ -149,17 +318,18 @@ impl TDAWm {
 
         self.load_window_properties(event.window);
 
-        // If the window is normal we shall set it
-        // as the new master
-        if matches!(
-            self.ctx
-                .windows_by_id
-                .get(&event.window)
-                .unwrap()
-                .window_type,
-            WindowType::Normal
-        ) {
-            self.current_layout.set_master(event.window);
+        // Windows that can't be resized (min size == max size) float,
+        // centered on the screen
+        if let Some(size) = self.server.get_fixed_size_hint(event.window) {
+            let screen = self.ctx.focused_screen(self.server.get_mouse_position());
+            let position = Position {
+                x: screen.x as i32 + (screen.width as i32 - size.x as i32) / 2,
+                y: screen.y as i32 + (screen.height as i32 - size.y as i32) / 2,
+            };
+            let window = self.ctx.windows_by_id.get_mut(&event.window).unwrap();
+            window.floating = true;
+            window.fixed_position = Some(position);
+            window.fixed_size = Some(size);
         }
         self.layout()?;
         Ok(())
@@ -167,42 +337,711 @@ impl TDAWm {
     fn unregister_window(&mut self, event: xlib::XEvent) -> Result<(), TDAWmError> {
         let event: xlib::XMapRequestEvent = From::from(event);
         info!("unregistering window with id {}", event.window);
-        self.ctx
-            .focused_screen_mut(self.server.get_mouse_position())
-            .current_workspace_mut()
-            .remove_window(&event.window);
+        self.closing.remove(&event.window);
+        let mut was_focused = false;
+        for screen in self.ctx.screens.iter_mut() {
+            if screen.focused_window == event.window {
+                screen.focused_window = 0;
+                was_focused = true;
+            }
+        }
+        // the window may be on any workspace, when it was the master
+        // the next window of that stack takes its place
+        for workspace in self
+            .ctx
+            .screens
+            .iter_mut()
+            .flat_map(|screen| screen.workspaces.iter_mut())
+        {
+            workspace.remove_window(&event.window);
+        }
 
         self.ctx.windows_by_id.remove(&event.window as &WindowId);
         self.layout()?;
+        if was_focused {
+            self.refocus();
+        }
         Ok(())
     }
 
     fn handle_keypress(&mut self, event: xlib::XEvent) -> Result<(), TDAWmError> {
         // converting event to good type
         let event: xlib::XKeyEvent = From::from(event);
-        trace!("keypress: {}", event.keycode);
-        // layout switch on ctrl+p
-        // for debug purposes right now.
-        if event.keycode == 33 {
-            if self.current_layout.id() == "horizontal" {
-                self.current_layout = Box::new(VerticalLayout::init());
-            } else {
-                self.current_layout = Box::new(HorizontalLayout::init());
+        trace!("keypress: {} (state {})", event.keycode, event.state);
+        let state = self.server.clean_modifiers(event.state);
+        let keysym = self.server.keysym_from_keycode(event.keycode as Keycode);
+        // While the keyboard is grabbed we also receive the modifiers
+        // being pressed on their own.
+        if !self.modes.is_default() && is_modifier_key(keysym) {
+            return Ok(());
+        }
+        let primary = self.config.primary_modifier();
+        // Keycodes are compared, like in grab_keys, because the keysym of a
+        // binding may not be on the first level of its key (digits on AZERTY).
+        let binding = self
+            .modes
+            .bindings(&self.config.keybinding)
+            .iter()
+            .find(|b| {
+                b.modifier_mask(primary) == state
+                    && x11::X11Adapter::keysym_from_name(b.key.get_ref())
+                        .and_then(|keysym| self.server.keycode_from_keysym(keysym))
+                        == Some(event.keycode as Keycode)
+            })
+            .cloned();
+        // A chord is left after one key, whether it matched or not.
+        let was_chord = self.modes.in_chord();
+        if was_chord {
+            self.pop_mode();
+        }
+        match binding {
+            Some(binding) if !binding.chord.is_empty() => {
+                self.push_mode(Mode {
+                    name: binding.describe(),
+                    bindings: binding.chord,
+                    oneshot: true,
+                });
             }
+            Some(binding) => {
+                if let Some(action) = binding.action() {
+                    self.execute_action(action)?;
+                }
+            }
+            None => {
+                if !was_chord && !self.modes.is_default() && keysym == keysym::XK_Escape as Keysym {
+                    self.pop_mode();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn push_mode(&mut self, mode: Mode) {
+        // without the grab the keys of the mode, Escape included,
+        // would never reach tdawm
+        if self.modes.is_default() && !self.server.grab_keyboard() {
+            warn!(
+                "unable to grab the keyboard, not entering mode {}",
+                mode.name
+            );
+            return;
+        }
+        info!("entering mode {}", mode.name);
+        self.modes.push(mode);
+        self.server.set_mode_property(self.modes.name());
+    }
+    fn pop_mode(&mut self) {
+        if let Some(mode) = self.modes.pop() {
+            info!("leaving mode {}", mode.name);
+        }
+        if self.modes.is_default() {
+            self.server.ungrab_keyboard();
+        }
+        self.server.set_mode_property(self.modes.name());
+    }
+
+    fn handle_button_press(&mut self, event: xlib::XEvent) -> Result<(), TDAWmError> {
+        let event: xlib::XButtonEvent = From::from(event);
+        trace!("button press: {} (state {})", event.button, event.state);
+        let state = self.server.clean_modifiers(event.state);
+        let primary = self.config.primary_modifier();
+        let Some(action) = self
+            .config
+            .mousebinding
+            .iter()
+            .find(|b| *b.button.get_ref() == event.button && b.modifier_mask(primary) == state)
+            .map(|b| b.action)
+        else {
+            return Ok(());
+        };
+        // The button is grabbed on the root window,
+        // subwindow is the client under the pointer.
+        let window_id = event.subwindow;
+        let Some(window) = self.ctx.windows_by_id.get(&window_id) else {
+            return Ok(());
+        };
+        if !matches!(window.window_type, WindowType::Normal) {
+            return Ok(());
+        }
+        let floating = window.floating;
+        let Some((position, size)) = self.server.get_window_geometry(window_id) else {
+            return Ok(());
+        };
+        self.drag = Some(Drag {
+            window: window_id,
+            action,
+            pointer: Position {
+                x: event.x_root,
+                y: event.y_root,
+            },
+            position,
+            size,
+        });
+        self.server.grab_pointer();
+        self.server.put_window_on_top(window_id);
+        if !floating
+            && (action == MouseAction::Resize || self.config.drag_tiled == DragTiled::Float)
+        {
+            let window = self.ctx.windows_by_id.get_mut(&window_id).unwrap();
+            window.floating = true;
+            window.fixed_position = Some(position);
+            window.fixed_size = Some(size);
             self.layout()?;
         }
-        if event.keycode == 36 {
-            //enter
-            debug!("starting alacritty");
-            Command::new("alacritty")
-                .spawn()
-                .expect("failed to execute process");
+        Ok(())
+    }
+
+    fn handle_motion(&mut self, event: xlib::XEvent) {
+        let event: xlib::XMotionEvent = From::from(event);
+        let Some(drag) = self.drag else {
+            return;
+        };
+        let pointer = Position {
+            x: event.x_root,
+            y: event.y_root,
+        };
+        let window = self.ctx.windows_by_id.get_mut(&drag.window);
+        let floating = window.as_ref().is_some_and(|w| w.floating);
+        match drag.action {
+            MouseAction::Move => {
+                let position = drag.moved_position(pointer);
+                self.server.move_window(drag.window, position.x, position.y);
+                if let Some(window) = window.filter(|_| floating) {
+                    window.fixed_position = Some(position);
+                }
+            }
+            MouseAction::Resize => {
+                let size = drag.resized_size(pointer);
+                self.server.resize_window(drag.window, size.x, size.y);
+                if let Some(window) = window.filter(|_| floating) {
+                    window.fixed_size = Some(size);
+                }
+            }
+        }
+    }
+
+    fn handle_button_release(&mut self, event: xlib::XEvent) -> Result<(), TDAWmError> {
+        let event: xlib::XButtonEvent = From::from(event);
+        let Some(drag) = self.drag.take() else {
+            return Ok(());
+        };
+        self.server.ungrab_pointer();
+        let Some(window) = self.ctx.windows_by_id.get(&drag.window) else {
+            return Ok(());
+        };
+        if window.floating {
+            return Ok(());
         }
-        // Number keys at the top of the keyboard
-        if event.keycode >= 10 && event.keycode <= 19 {
-            let wc_id = event.keycode as u32 - 10;
-            trace!("switching to workspace {}", wc_id);
-            self.switch_workspace(wc_id as usize)?;
+        // A tiled window takes the place of the tile it is dropped on
+        let pointer = Position {
+            x: event.x_root,
+            y: event.y_root,
+        };
+        if let Some(target) = self.tiled_window_at(pointer, drag.window) {
+            debug!("swapping windows {} and {}", drag.window, target);
+            if let Some(screen) = self
+                .ctx
+                .screens
+                .iter_mut()
+                .find(|screen| screen.has_window_visible(drag.window))
+            {
+                screen
+                    .current_workspace_mut()
+                    .swap_windows(drag.window, target);
+            }
+        }
+        self.layout()
+    }
+
+    fn tiled_window_at(&self, pointer: Position, except: WindowId) -> Option<WindowId> {
+        let screen = self
+            .ctx
+            .screens
+            .iter()
+            .find(|screen| screen.has_window_visible(except))?;
+        screen
+            .current_workspace()
+            .iter_normal_windows(&self.ctx.windows_by_id)
+            .map(|w| w.id)
+            .filter(|id| *id != except)
+            .find(|id| {
+                self.server.get_window_geometry(*id).is_some_and(|(p, s)| {
+                    pointer.x >= p.x
+                        && pointer.x < p.x + s.x as i32
+                        && pointer.y >= p.y
+                        && pointer.y < p.y + s.y as i32
+                })
+            })
+    }
+
+    // Every action goes through here, whether it comes from
+    // a keybinding or from `tdawm msg`.
+    fn execute_action(&mut self, action: Action) -> Result<(), TDAWmError> {
+        debug!("executing action {}", action);
+        match action {
+            Action::ToggleLayout => {
+                let workspace = self
+                    .ctx
+                    .focused_screen_mut(self.server.get_mouse_position())
+                    .current_workspace_mut();
+                if workspace.layout.id() == "horizontal" {
+                    workspace.layout = Box::new(VerticalLayout::init());
+                } else {
+                    workspace.layout = Box::new(HorizontalLayout::init());
+                }
+                self.layout()?;
+            }
+            Action::SetLayout(id) => self.set_layout(&id)?,
+            Action::NextLayout => self.cycle_layout(1)?,
+            Action::PrevLayout => self.cycle_layout(-1)?,
+            Action::Spawn(cmd) => {
+                if let Err(e) = self.processes.spawn(&cmd) {
+                    error!("unable to start {}: {}", cmd, e);
+                }
+            }
+            Action::Workspace(number) => {
+                trace!("switching to workspace {}", number);
+                let mut index = number.saturating_sub(1);
+                let screen = self.ctx.focused_screen(self.server.get_mouse_position());
+                // like i3's workspace_auto_back_and_forth
+                if index == screen.current_workspace_id && self.config.auto_back_and_forth {
+                    if let Some(previous) = screen.previous_workspace() {
+                        index = previous;
+                    }
+                }
+                self.switch_workspace(index)?;
+            }
+            Action::PreviousWorkspace => {
+                if let Some(previous) = self
+                    .ctx
+                    .focused_screen(self.server.get_mouse_position())
+                    .previous_workspace()
+                {
+                    self.switch_workspace(previous)?;
+                }
+            }
+            Action::MoveToWorkspace(number) => {
+                self.move_to_workspace(number.saturating_sub(1), false)?
+            }
+            Action::MoveToWorkspaceAndFollow(number) => {
+                self.move_to_workspace(number.saturating_sub(1), true)?
+            }
+            Action::EnterMode(name) => {
+                match self.config.mode.iter().find(|m| *m.name.get_ref() == name) {
+                    Some(mode) => {
+                        let mode = Mode {
+                            name: mode.name.get_ref().clone(),
+                            bindings: mode.keybinding.clone(),
+                            oneshot: false,
+                        };
+                        self.push_mode(mode);
+                    }
+                    None => error!("unknown mode {}", name),
+                }
+            }
+            Action::FocusNext => self.focus_relative(1),
+            Action::FocusPrev => self.focus_relative(-1),
+            Action::FocusMaster => {
+                if let Some(master) = self.focus_order().first() {
+                    self.focus(*master);
+                }
+            }
+            Action::FocusDirection(direction) => self.focus_direction(direction),
+            Action::SwapDirection(direction) => self.swap_direction(direction)?,
+            Action::SwapNext => self.swap_relative(1)?,
+            Action::SwapPrev => self.swap_relative(-1)?,
+            Action::Zoom => self.zoom()?,
+            Action::RotateNext => self.rotate(true)?,
+            Action::RotatePrev => self.rotate(false)?,
+            Action::Close => self.close_window(),
+            Action::ToggleFloating => self.toggle_floating()?,
+            Action::ToggleFullscreen => {
+                let window = self
+                    .ctx
+                    .focused_screen(self.server.get_mouse_position())
+                    .focused_window;
+                if let Some(fullscreen) = self.ctx.windows_by_id.get(&window).map(|w| w.fullscreen)
+                {
+                    self.set_fullscreen(window, !fullscreen)?;
+                }
+            }
+            Action::IncNmaster | Action::DecNmaster | Action::IncMfact | Action::DecMfact => {
+                let workspace = self
+                    .ctx
+                    .focused_screen_mut(self.server.get_mouse_position())
+                    .current_workspace_mut();
+                match action {
+                    Action::IncNmaster => workspace.nmaster += 1,
+                    Action::DecNmaster => workspace.nmaster = workspace.nmaster.saturating_sub(1),
+                    Action::IncMfact => {
+                        workspace.mfact = (workspace.mfact + MFACT_STEP).min(Mfact::MAX)
+                    }
+                    _ => workspace.mfact = (workspace.mfact - MFACT_STEP).max(Mfact::MIN),
+                }
+                self.layout()?;
+            }
+            Action::ExitMode => self.pop_mode(),
+            Action::Reload => self.reload_config()?,
+        }
+        Ok(())
+    }
+
+    fn focus(&mut self, window: WindowId) {
+        let mouse = self.server.get_mouse_position();
+        match self
+            .ctx
+            .screens
+            .iter_mut()
+            .find(|screen| screen.has_window_visible(window))
+        {
+            Some(screen) => {
+                screen.focused_window = window;
+                screen.current_workspace_mut().remember_focus(window);
+            }
+            None => self.ctx.focused_screen_mut(mouse).focused_window = window,
+        }
+        self.server.focus_window(window);
+    }
+
+    // Gives the focus back to the window last focused on the current
+    // workspace, or to its first window when that one is gone
+    fn refocus(&mut self) {
+        let last = self
+            .ctx
+            .focused_screen(self.server.get_mouse_position())
+            .current_workspace()
+            .last_focused();
+        match last {
+            Some(window) => self.focus(window),
+            None if self.focus_order().is_empty() => self.unfocus(),
+            None => self.focus_relative(0),
+        }
+    }
+
+    // Nothing left to focus on the screen, the keyboard goes to the root window
+    fn unfocus(&mut self) {
+        self.ctx
+            .focused_screen_mut(self.server.get_mouse_position())
+            .focused_window = 0;
+        self.server.focus_window(self.server.root_window);
+    }
+
+    // The screen under the pointer is the focused one,
+    // so the pointer follows the focus onto another screen
+    fn warp_to(&mut self, window: WindowId) {
+        let mouse = self.server.get_mouse_position();
+        if self.ctx.focused_screen(mouse).has_window_visible(window) {
+            return;
+        }
+        if let Some((_, size)) = self.server.get_window_geometry(window) {
+            self.server
+                .warp_pointer(window, size.x as i32 / 2, size.y as i32 / 2);
+        }
+    }
+
+    // Closest visible window in the direction from the focused one,
+    // looked for on the focused screen first, then on the other screens
+    fn neighbor(&self, direction: Direction, tiled_only: bool) -> Option<WindowId> {
+        let focused = self
+            .ctx
+            .focused_screen(self.server.get_mouse_position())
+            .focused_window;
+        // nothing may be focused (0) or the window may be gone,
+        // asking X for its geometry would then fail
+        if !self.ctx.windows_by_id.contains_key(&focused) {
+            return None;
+        }
+        let current = self
+            .ctx
+            .screens
+            .iter()
+            .position(|screen| screen.has_window_visible(focused))?;
+        let from = self.server.get_window_geometry(focused)?;
+        let closest = |same_screen: bool| {
+            self.ctx
+                .screens
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| (*i == current) == same_screen)
+                .flat_map(|(_, screen)| screen.current_workspace().windows.iter())
+                .filter(|id| {
+                    **id != focused
+                        && self.ctx.windows_by_id.get(id).is_some_and(|w| {
+                            matches!(w.window_type, WindowType::Normal)
+                                && (!tiled_only || !(w.floating || w.fullscreen))
+                        })
+                })
+                .filter_map(|id| {
+                    let to = self.server.get_window_geometry(*id)?;
+                    Some((*id, direction.distance(from, to)?))
+                })
+                .min_by_key(|(_, distance)| *distance)
+                .map(|(id, _)| id)
+        };
+        closest(true).or_else(|| closest(false))
+    }
+
+    fn focus_direction(&mut self, direction: Direction) {
+        if let Some(window) = self.neighbor(direction, false) {
+            self.focus(window);
+            self.warp_to(window);
+        }
+    }
+
+    // Tiled windows exchange their places, even when they are
+    // on the workspaces of two different screens
+    fn swap_direction(&mut self, direction: Direction) -> Result<(), TDAWmError> {
+        let (_, Some(focused)) = self.focused_tiled_window() else {
+            return Ok(());
+        };
+        let Some(other) = self.neighbor(direction, true) else {
+            return Ok(());
+        };
+        let place = |window: WindowId, screens: &[Screen]| {
+            screens.iter().enumerate().find_map(|(i, screen)| {
+                let index = screen
+                    .current_workspace()
+                    .windows
+                    .iter()
+                    .position(|w| *w == window)?;
+                Some((i, index))
+            })
+        };
+        let (Some((screen_a, index_a)), Some((screen_b, index_b))) = (
+            place(focused, &self.ctx.screens),
+            place(other, &self.ctx.screens),
+        ) else {
+            return Ok(());
+        };
+        self.ctx.screens[screen_a].current_workspace_mut().windows[index_a] = other;
+        self.ctx.screens[screen_b].current_workspace_mut().windows[index_b] = focused;
+        self.layout()?;
+        self.focus(focused);
+        self.warp_to(focused);
+        Ok(())
+    }
+
+    // Windows of the focused workspace in the order the layout places them,
+    // followed by the floating ones
+    fn focus_order(&self) -> Vec<WindowId> {
+        let workspace = self
+            .ctx
+            .focused_screen(self.server.get_mouse_position())
+            .current_workspace();
+        let mut order: Vec<WindowId> = workspace
+            .iter_normal_windows(&self.ctx.windows_by_id)
+            .map(|w| w.id)
+            .collect();
+        order.extend(workspace.windows.iter().filter(|id| {
+            self.ctx.windows_by_id.get(id).is_some_and(|w| {
+                matches!(w.window_type, WindowType::Normal) && (w.floating || w.fullscreen)
+            })
+        }));
+        order
+    }
+
+    fn focus_relative(&mut self, step: isize) {
+        let order = self.focus_order();
+        if order.is_empty() {
+            return;
+        }
+        let focused = self
+            .ctx
+            .focused_screen(self.server.get_mouse_position())
+            .focused_window;
+        let next = match order.iter().position(|w| *w == focused) {
+            Some(index) => (index as isize + step).rem_euclid(order.len() as isize) as usize,
+            // nothing focused on this workspace yet
+            None => 0,
+        };
+        self.focus(order[next]);
+    }
+
+    // A window starts floating where it is currently shown
+    fn toggle_floating(&mut self) -> Result<(), TDAWmError> {
+        let window_id = self
+            .ctx
+            .focused_screen(self.server.get_mouse_position())
+            .focused_window;
+        let Some(window) = self.ctx.windows_by_id.get_mut(&window_id) else {
+            return Ok(());
+        };
+        if !matches!(window.window_type, WindowType::Normal) {
+            return Ok(());
+        }
+        if !window.floating {
+            let Some((position, size)) = self.server.get_window_geometry(window_id) else {
+                return Ok(());
+            };
+            window.fixed_position = Some(position);
+            window.fixed_size = Some(size);
+        }
+        window.floating = !window.floating;
+        info!("window {} floating: {}", window_id, window.floating);
+        self.layout()
+    }
+
+    // Requests sent by clients, see
+    // https://specifications.freedesktop.org/wm-spec/1.3/ar01s05.html#id-1.6.8
+    fn handle_client_message(
+        &mut self,
+        event: xlib::XClientMessageEvent,
+    ) -> Result<(), TDAWmError> {
+        let net_wm_state = self
+            .server
+            .atom_manager
+            .get_atom("_NET_WM_STATE", self.server.display);
+        let fullscreen_state = self
+            .server
+            .atom_manager
+            .get_atom("_NET_WM_STATE_FULLSCREEN", self.server.display);
+        if event.message_type != net_wm_state {
+            return Ok(());
+        }
+        // action, first property, second property, ...
+        let data = event.data.as_longs();
+        if data[1] as xlib::Atom != fullscreen_state && data[2] as xlib::Atom != fullscreen_state {
+            return Ok(());
+        }
+        let Some(window) = self.ctx.windows_by_id.get(&event.window) else {
+            return Ok(());
+        };
+        let fullscreen = match data[0] {
+            // _NET_WM_STATE_REMOVE, _NET_WM_STATE_ADD, _NET_WM_STATE_TOGGLE
+            0 => false,
+            1 => true,
+            2 => !window.fullscreen,
+            _ => return Ok(()),
+        };
+        self.set_fullscreen(event.window, fullscreen)
+    }
+
+    // The window leaves the layout while it is fullscreen and gets
+    // its place back (tiled or floating) afterwards
+    fn set_fullscreen(&mut self, window_id: WindowId, fullscreen: bool) -> Result<(), TDAWmError> {
+        let Some(window) = self.ctx.windows_by_id.get_mut(&window_id) else {
+            return Ok(());
+        };
+        if window.fullscreen == fullscreen {
+            return Ok(());
+        }
+        info!("window {} fullscreen: {}", window_id, fullscreen);
+        window.fullscreen = fullscreen;
+        if fullscreen {
+            self.server
+                .add_wm_state(window_id, "_NET_WM_STATE_FULLSCREEN");
+        } else {
+            self.server
+                .remove_wm_state(window_id, "_NET_WM_STATE_FULLSCREEN");
+        }
+        self.layout()
+    }
+
+    // Windows supporting WM_DELETE_WINDOW are asked to close,
+    // the others are killed right away
+    fn close_window(&mut self) {
+        let window = self
+            .ctx
+            .focused_screen(self.server.get_mouse_position())
+            .focused_window;
+        if !self.ctx.windows_by_id.contains_key(&window) {
+            return;
+        }
+        if self.server.supports_protocol(window, "WM_DELETE_WINDOW") {
+            info!("asking window {} to close", window);
+            self.server.send_protocol(window, "WM_DELETE_WINDOW");
+            if let Some(timeout) = self.config.kill_timeout {
+                self.closing
+                    .insert(window, Instant::now() + Duration::from_secs(timeout));
+            }
+        } else {
+            info!("killing window {}", window);
+            self.server.kill_client(window);
+        }
+    }
+
+    fn kill_unresponsive(&mut self) {
+        let now = Instant::now();
+        let expired: Vec<WindowId> = self
+            .closing
+            .iter()
+            .filter(|(_, deadline)| **deadline <= now)
+            .map(|(window, _)| *window)
+            .collect();
+        for window in expired {
+            self.closing.remove(&window);
+            warn!("window {} did not close in time, killing it", window);
+            self.server.kill_client(window);
+        }
+    }
+
+    // Tiled windows of the focused workspace in layout order, and the
+    // focused one if it is among them
+    fn focused_tiled_window(&self) -> (Vec<WindowId>, Option<WindowId>) {
+        let screen = self.ctx.focused_screen(self.server.get_mouse_position());
+        let tiled: Vec<WindowId> = screen
+            .current_workspace()
+            .iter_normal_windows(&self.ctx.windows_by_id)
+            .map(|w| w.id)
+            .collect();
+        let focused = Some(screen.focused_window).filter(|w| tiled.contains(w));
+        (tiled, focused)
+    }
+
+    fn swap_relative(&mut self, step: isize) -> Result<(), TDAWmError> {
+        let (tiled, Some(focused)) = self.focused_tiled_window() else {
+            return Ok(());
+        };
+        let index = tiled.iter().position(|w| *w == focused).unwrap();
+        let other = tiled[(index as isize + step).rem_euclid(tiled.len() as isize) as usize];
+        if other == focused {
+            return Ok(());
+        }
+        self.ctx
+            .focused_screen_mut(self.server.get_mouse_position())
+            .current_workspace_mut()
+            .swap_windows(focused, other);
+        self.layout()
+    }
+
+    fn zoom(&mut self) -> Result<(), TDAWmError> {
+        let (tiled, Some(mut window)) = self.focused_tiled_window() else {
+            return Ok(());
+        };
+        if tiled.first() == Some(&window) {
+            // already the master, like dwm the next window takes its place
+            let Some(next) = tiled.get(1) else {
+                return Ok(());
+            };
+            window = *next;
+        }
+        self.ctx
+            .focused_screen_mut(self.server.get_mouse_position())
+            .current_workspace_mut()
+            .move_to_front(window);
+        self.layout()
+    }
+
+    fn rotate(&mut self, forward: bool) -> Result<(), TDAWmError> {
+        let (tiled, _) = self.focused_tiled_window();
+        self.ctx
+            .focused_screen_mut(self.server.get_mouse_position())
+            .current_workspace_mut()
+            .rotate(&tiled, forward);
+        self.layout()
+    }
+
+    // Commands sent by `tdawm msg` are appended to a property of the root window.
+    fn handle_root_property(&mut self, event: xlib::XPropertyEvent) -> Result<(), TDAWmError> {
+        if event.state != xlib::PropertyNewValue || !self.server.is_command_property(event.atom) {
+            return Ok(());
+        }
+        for command in self.server.take_commands() {
+            info!("received command {}", command);
+            match command.parse::<Action>() {
+                Ok(action) => self.execute_action(action)?,
+                Err(e) => error!("invalid command {}: {}", command, e),
+            }
         }
         Ok(())
     }
@@ -211,12 +1050,74 @@ impl TDAWm {
         if let Some(window) = self.ctx.windows_by_id.get_mut(&window_id) {
             let window_type = window.get_window_type(&mut self.server);
             window.window_type = window_type;
+            // the window may ask to be fullscreen before being mapped
+            window.fullscreen = self
+                .server
+                .has_wm_state(window_id, "_NET_WM_STATE_FULLSCREEN");
         }
     }
 
+    fn set_layout(&mut self, id: &str) -> Result<(), TDAWmError> {
+        let Some(layout) = layout_from_id(id) else {
+            error!("unknown layout {}", id);
+            return Ok(());
+        };
+        self.ctx
+            .focused_screen_mut(self.server.get_mouse_position())
+            .current_workspace_mut()
+            .layout = layout;
+        self.layout()
+    }
+
+    // Goes to the next layout of the configured cycle, a workspace using
+    // a layout out of the cycle goes to its first layout
+    fn cycle_layout(&mut self, step: isize) -> Result<(), TDAWmError> {
+        let cycle = &self.config.layout.cycle;
+        if cycle.is_empty() {
+            return Ok(());
+        }
+        let current = self
+            .ctx
+            .focused_screen(self.server.get_mouse_position())
+            .current_workspace()
+            .layout
+            .id();
+        let next = match cycle.iter().position(|id| *id.get_ref() == current) {
+            Some(index) => (index as isize + step).rem_euclid(cycle.len() as isize) as usize,
+            None => 0,
+        };
+        let id = cycle[next].get_ref().clone();
+        self.set_layout(&id)
+    }
+
     fn layout(&mut self) -> Result<(), TDAWmError> {
-        self.current_layout
-            .layout(&mut self.server, &mut self.ctx)?;
+        for screen in self.ctx.screens.iter() {
+            screen.current_workspace().layout.layout(
+                &mut self.server,
+                screen,
+                &self.ctx.windows_by_id,
+            )?;
+        }
+
+        // Floating windows keep their own geometry, above the tiled ones
+        for window_id in self
+            .ctx
+            .focused_screen(self.server.get_mouse_position())
+            .current_workspace()
+            .windows
+            .iter()
+        {
+            let window = self.ctx.windows_by_id.get(window_id).unwrap();
+            if window.floating && !window.fullscreen {
+                if let Some(p) = window.fixed_position {
+                    self.server.move_window(*window_id, p.x, p.y);
+                }
+                if let Some(s) = window.fixed_size {
+                    self.server.resize_window(*window_id, s.x, s.y);
+                }
+                self.server.put_window_on_top(*window_id);
+            }
+        }
 
         //TODO: Fix this for multi screen compliance
         // EWMH compliance. Windows can ask to be always on top
@@ -230,25 +1131,42 @@ impl TDAWm {
             .iter()
         {
             let window = self.ctx.windows_by_id.get(window_id).unwrap();
-            match window.window_type {
-                WindowType::Dock => {
-                    // A dock window can be placed without respecting the layout.
-                    if let Some(p) = window.fixed_position {
-                        self.server.move_window(*window_id, p.x, p.y);
-                    }
-                    if let Some(s) = window.fixed_size {
-                        self.server.resize_window(*window_id, s.x, s.y);
-                    }
+            if let WindowType::Dock = window.window_type {
+                // A dock window can be placed without respecting the layout.
+                if let Some(p) = window.fixed_position {
+                    self.server.move_window(*window_id, p.x, p.y);
+                }
+                if let Some(s) = window.fixed_size {
+                    self.server.resize_window(*window_id, s.x, s.y);
+                }
 
-                    // Dock windows should always be on top
+                // Dock windows should always be on top
+                self.server.put_window_on_top(*window_id);
+            }
+        }
+
+        // Fullscreen windows cover their screen, docks included
+        for screen in self.ctx.screens.iter() {
+            for window_id in screen.current_workspace().windows.iter() {
+                if self.ctx.windows_by_id.get(window_id).unwrap().fullscreen {
+                    self.server
+                        .move_window(*window_id, screen.x as i32, screen.y as i32);
+                    self.server
+                        .resize_window(*window_id, screen.width, screen.height);
                     self.server.put_window_on_top(*window_id);
                 }
-                _ => {}
             }
         }
+        // Windows mapped or moved under the pointer send EnterNotify,
+        // they must not take the focus from the window chosen by tdawm
+        self.server.discard_enter_events();
         Ok(())
     }
     fn switch_workspace(&mut self, index: usize) -> Result<(), TDAWmError> {
+        let screen = self.ctx.focused_screen(self.server.get_mouse_position());
+        if index == screen.current_workspace_id || index >= screen.workspaces.len() {
+            return Ok(());
+        }
         info!("Going to workspace {}", index);
         info!(
             "count: {}",
@@ -267,19 +1185,54 @@ impl TDAWm {
         {
             self.server.hide_window(*window_id);
         }
-        self.server.focus_window(self.server.root_window);
-        if let Some(_ws) = self
+        self.unfocus();
+        self.ctx
+            .focused_screen_mut(self.server.get_mouse_position())
+            .set_current_workspace(index);
+        self.server.ewmh_set_current_desktop(index);
+        self.layout()?;
+        self.refocus();
+        Ok(())
+    }
+
+    fn move_to_workspace(&mut self, index: usize, follow: bool) -> Result<(), TDAWmError> {
+        let new_window = self.config.new_window;
+        let screen = self
             .ctx
-            .focused_screen(self.server.get_mouse_position())
-            .workspaces
-            .get(index)
+            .focused_screen_mut(self.server.get_mouse_position());
+        let window = screen.focused_window;
+        if index == screen.current_workspace_id
+            || index >= screen.workspaces.len()
+            || !screen.current_workspace().windows.contains(&window)
         {
-            info!("found workspace {}", index);
-            self.ctx
-                .focused_screen_mut(self.server.get_mouse_position())
-                .current_workspace_id = index;
+            return Ok(());
         }
-        self.server.ewmh_set_current_desktop(index);
-        self.layout()
+        info!("Moving window {} to workspace {}", window, index);
+        screen.current_workspace_mut().remove_window(&window);
+        // no window of the target workspace is focused, "after-focused" puts it at the end
+        screen.workspaces[index].insert_window(window, new_window, 0);
+
+        if follow {
+            // the window is no longer on the current workspace,
+            // so it stays mapped while the others are hidden
+            self.switch_workspace(index)?;
+            self.focus(window);
+            return Ok(());
+        }
+        self.server.hide_window(window);
+        self.server.focus_window(self.server.root_window);
+        self.ctx
+            .focused_screen_mut(self.server.get_mouse_position())
+            .focused_window = 0;
+        self.layout()?;
+        self.refocus();
+        Ok(())
     }
 }
+
+// Shift, Control, Alt, Super, ... and AltGr
+fn is_modifier_key(keysym: Keysym) -> bool {
+    (keysym::XK_Shift_L as Keysym..=keysym::XK_Hyper_R as Keysym).contains(&keysym)
+        || keysym == keysym::XK_ISO_Level3_Shift as Keysym
+        || keysym == keysym::XK_Mode_switch as Keysym
+}
@@ -6,6 +6,11 @@ pub struct Window {
     pub fixed_position: Option<Position>,
     pub fixed_size: Option<Size>,
     pub window_type: WindowType,
+    // floating windows are not tiled by the layouts
+    // and keep their fixed position and size
+    pub floating: bool,
+    // fullscreen windows cover their screen, above the docks
+    pub fullscreen: bool,
 }
 #[derive(Debug, Clone, Copy)]
 pub struct Position {
@@ -38,7 +43,7 @@ impl std::cmp::PartialEq for Window {
 }
 impl std::cmp::PartialOrd for Window {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.id.partial_cmp(&other.id)
+        Some(self.cmp(other))
     }
 }
 
@@ -1,21 +1,44 @@
-use std::collections::{BTreeSet, HashMap};
+use std::collections::HashMap;
 
-use log::error;
-
-use crate::tdawm::WindowType;
+use crate::{
+    config::{LayoutConfig, NewWindow},
+    layouts::{layout_from_id, DWMLayout, Layout},
+    tdawm::WindowType,
+};
 
 use super::{Window, WindowId};
 #[derive(Debug)]
 pub struct Workspace {
-    // simple ordered set of windows
-    pub windows: BTreeSet<WindowId>,
+    // windows in the order they are tiled,
+    // the first tiled one is the master
+    pub windows: Vec<WindowId>,
+    pub layout: Box<dyn Layout>,
+    // windows focused on this workspace, the most recent last
+    pub focus_history: Vec<WindowId>,
+    // windows in the master area of the dwm layout
+    pub nmaster: usize,
+    // part of the screen width given to the master area
+    pub mfact: f32,
 }
 
 impl Workspace {
     pub fn new() -> Workspace {
+        let defaults = LayoutConfig::default();
         Workspace {
-            windows: BTreeSet::new(),
+            windows: Vec::new(),
+            layout: Box::new(DWMLayout::init()),
+            focus_history: Vec::new(),
+            nmaster: defaults.nmaster,
+            mfact: defaults.mfact.0,
+        }
+    }
+    /// `number` is the number of the workspace, starting at 1.
+    pub fn apply_layout_config(&mut self, config: &LayoutConfig, number: usize) {
+        if let Some(layout) = layout_from_id(config.workspace_layout(number)) {
+            self.layout = layout;
         }
+        self.nmaster = config.nmaster;
+        self.mfact = config.mfact.0;
     }
     pub fn iter_normal_windows<'a>(
         &'a self,
@@ -23,16 +46,77 @@ impl Workspace {
     ) -> impl Iterator<Item = &'a Window> + 'a {
         self.windows
             .iter()
-            .map(|w_id| {
-                error!("id: {}, {:?}", w_id, windows.get(&w_id));
-                windows.get(&w_id).unwrap()
+            .filter_map(|w_id| windows.get(w_id))
+            .filter(move |w| {
+                matches!(w.window_type, WindowType::Normal) && !w.floating && !w.fullscreen
             })
-            .filter(move |w| matches!(w.window_type, WindowType::Normal))
     }
-    pub fn add_window(&mut self, window: WindowId) {
-        self.windows.insert(window);
+    pub fn insert_window(&mut self, window: WindowId, position: NewWindow, focused: WindowId) {
+        if self.windows.contains(&window) {
+            return;
+        }
+        let index = match position {
+            NewWindow::Master => 0,
+            NewWindow::AfterFocused => self
+                .windows
+                .iter()
+                .position(|w| *w == focused)
+                .map_or(self.windows.len(), |i| i + 1),
+            NewWindow::End => self.windows.len(),
+        };
+        self.windows.insert(index, window);
     }
     pub fn remove_window(&mut self, window: &WindowId) {
-        self.windows.remove(window);
+        self.windows.retain(|w| w != window);
+        self.focus_history.retain(|w| w != window);
+    }
+    pub fn remember_focus(&mut self, window: WindowId) {
+        self.focus_history.retain(|w| *w != window);
+        self.focus_history.push(window);
+    }
+    /// The most recently focused window still on this workspace.
+    pub fn last_focused(&self) -> Option<WindowId> {
+        self.focus_history
+            .iter()
+            .rev()
+            .find(|w| self.windows.contains(w))
+            .copied()
+    }
+    pub fn swap_windows(&mut self, a: WindowId, b: WindowId) {
+        let a = self.windows.iter().position(|w| *w == a);
+        let b = self.windows.iter().position(|w| *w == b);
+        if let (Some(a), Some(b)) = (a, b) {
+            self.windows.swap(a, b);
+        }
+    }
+    /// Puts the window at the top of the stack.
+    pub fn move_to_front(&mut self, window: WindowId) {
+        if let Some(index) = self.windows.iter().position(|w| *w == window) {
+            let window = self.windows.remove(index);
+            self.windows.insert(0, window);
+        }
+    }
+    /// Rotates the given windows among the places they hold in the stack,
+    /// the other windows stay where they are.
+    pub fn rotate(&mut self, windows: &[WindowId], forward: bool) {
+        let places: Vec<usize> = self
+            .windows
+            .iter()
+            .enumerate()
+            .filter(|(_, w)| windows.contains(w))
+            .map(|(i, _)| i)
+            .collect();
+        let mut rotated: Vec<WindowId> = places.iter().map(|i| self.windows[*i]).collect();
+        if rotated.is_empty() {
+            return;
+        }
+        if forward {
+            rotated.rotate_right(1);
+        } else {
+            rotated.rotate_left(1);
+        }
+        for (place, window) in places.into_iter().zip(rotated) {
+            self.windows[place] = window;
+        }
     }
 }
@@ -1,12 +1,13 @@
 use core::slice;
 use std::{
     collections::HashMap,
-    ffi::{c_long, c_uchar, CStr, CString, NulError},
+    ffi::{c_char, c_long, c_uchar, CStr, CString, NulError},
     mem::zeroed,
     ptr,
+    time::Duration,
 };
 
-use log::{error, info, trace};
+use log::{info, trace, warn};
 use thiserror::Error;
 use x11::{
     xinerama,
@@ -14,12 +15,14 @@ use x11::{
 };
 
 use crate::tdawm::{self, Screen, WindowType};
-use crate::tdawm::{Window, WindowId};
+use crate::tdawm::{Position, Size, Window, WindowId};
 
 pub struct X11Adapter {
     pub display: *mut xlib::Display,
     pub atom_manager: AtomManager,
     pub root_window: WindowId,
+    // CapsLock, NumLock and ScrollLock bits, ignored in key bindings
+    lock_masks: u32,
 }
 #[derive(Debug, Error)]
 pub enum X11Error {
@@ -38,11 +41,14 @@ impl X11Adapter {
         }
         let am = AtomManager::new();
         let root_window = unsafe { xlib::XDefaultRootWindow(display) };
-        Ok(X11Adapter {
+        let mut adapter = X11Adapter {
             display,
             atom_manager: am,
             root_window,
-        })
+            lock_masks: xlib::LockMask,
+        };
+        adapter.load_lock_masks();
+        Ok(adapter)
     }
     pub fn init(&mut self) -> Vec<Screen> {
         info!("registering to x11 as a window manager");
@@ -54,42 +60,246 @@ impl X11Adapter {
                 xlib::SubstructureRedirectMask
                     | xlib::SubstructureNotifyMask
                     | xlib::StructureNotifyMask
-                    | xlib::EnterWindowMask,
+                    | xlib::EnterWindowMask
+                    | xlib::PropertyChangeMask,
             );
+            // Another window manager makes XSelectInput fail, the default
+            // handler exits. Later errors, usually about a window destroyed
+            // before tdawm saw it, are only logged.
+            xlib::XSync(self.display, 0);
+            xlib::XSetErrorHandler(Some(on_x_error));
         }
+        self.ewmh_init();
         self.load_screens()
     }
-    pub fn next_event(&self) -> xlib::XEvent {
-        let mut event: xlib::XEvent = unsafe { zeroed() };
+    /// Waits for the next event, at most `timeout` so the caller
+    /// can do some work (signals, ...) even if X is quiet.
+    pub fn next_event(&self, timeout: Duration) -> Option<xlib::XEvent> {
         unsafe {
+            if xlib::XPending(self.display) == 0 {
+                let mut fd = libc::pollfd {
+                    fd: xlib::XConnectionNumber(self.display),
+                    events: libc::POLLIN,
+                    revents: 0,
+                };
+                libc::poll(&mut fd, 1, timeout.as_millis() as libc::c_int);
+                if xlib::XPending(self.display) == 0 {
+                    return None;
+                }
+            }
+            let mut event: xlib::XEvent = zeroed();
             xlib::XNextEvent(self.display, &mut event);
+            Some(event)
+        }
+    }
+    /// Waits for X to process every request and drops the
+    /// EnterNotify events they caused.
+    pub fn discard_enter_events(&self) {
+        unsafe {
+            xlib::XSync(self.display, 0);
+            let mut event: xlib::XEvent = zeroed();
+            while xlib::XCheckMaskEvent(self.display, xlib::EnterWindowMask, &mut event) != 0 {}
         }
-        event
     }
+    /// Grabs the key with this modifier, whatever the state of
+    /// CapsLock, NumLock and ScrollLock.
     pub fn grab_key(&self, keycode: tdawm::Keycode, modifier: u32) {
         trace!("grabbing key {} with modifier {}", keycode, modifier);
+        for modifier in self.with_lock_combinations(modifier) {
+            unsafe {
+                xlib::XGrabKey(
+                    self.display,
+                    keycode,
+                    modifier,
+                    xlib::XDefaultRootWindow(self.display),
+                    0,
+                    xlib::GrabModeAsync,
+                    xlib::GrabModeAsync,
+                );
+            }
+        }
+    }
+    /// Grabs the button with this modifier on every window, whatever
+    /// the state of CapsLock, NumLock and ScrollLock.
+    pub fn grab_button(&self, button: u32, modifier: u32) {
+        trace!("grabbing button {} with modifier {}", button, modifier);
+        for modifier in self.with_lock_combinations(modifier) {
+            unsafe {
+                xlib::XGrabButton(
+                    self.display,
+                    button,
+                    modifier,
+                    self.root_window,
+                    0,
+                    (xlib::ButtonPressMask | xlib::ButtonReleaseMask | xlib::PointerMotionMask)
+                        as u32,
+                    xlib::GrabModeAsync,
+                    xlib::GrabModeAsync,
+                    0,
+                    0,
+                );
+            }
+        }
+    }
+    pub fn ungrab_buttons(&self) {
+        trace!("ungrabbing all buttons");
         unsafe {
-            xlib::XGrabKey(
+            xlib::XUngrabButton(
                 self.display,
-                keycode,
-                modifier,
-                xlib::XDefaultRootWindow(self.display),
+                xlib::AnyButton as u32,
+                xlib::AnyModifier,
+                self.root_window,
+            );
+        }
+    }
+    /// Sends pointer motion and button release to tdawm until
+    /// `ungrab_pointer` is called.
+    pub fn grab_pointer(&self) {
+        trace!("grabbing pointer");
+        unsafe {
+            xlib::XGrabPointer(
+                self.display,
+                self.root_window,
                 0,
+                (xlib::ButtonReleaseMask | xlib::PointerMotionMask) as u32,
                 xlib::GrabModeAsync,
                 xlib::GrabModeAsync,
+                0,
+                0,
+                xlib::CurrentTime,
             );
         }
     }
+    pub fn ungrab_pointer(&self) {
+        trace!("ungrabbing pointer");
+        unsafe {
+            xlib::XUngrabPointer(self.display, xlib::CurrentTime);
+        }
+    }
+    /// Sends every key press to tdawm until `ungrab_keyboard` is called.
+    /// Returns false when another client holds the keyboard.
+    pub fn grab_keyboard(&self) -> bool {
+        trace!("grabbing keyboard");
+        let status = unsafe {
+            xlib::XGrabKeyboard(
+                self.display,
+                self.root_window,
+                0,
+                xlib::GrabModeAsync,
+                xlib::GrabModeAsync,
+                xlib::CurrentTime,
+            )
+        };
+        status == xlib::GrabSuccess
+    }
+    pub fn ungrab_keyboard(&self) {
+        trace!("ungrabbing keyboard");
+        unsafe {
+            xlib::XUngrabKeyboard(self.display, xlib::CurrentTime);
+        }
+    }
+    /// Removes the lock bits and anything that is not a keyboard
+    /// modifier (mouse buttons) from an event state.
+    pub fn clean_modifiers(&self, state: u32) -> u32 {
+        state
+            & !self.lock_masks
+            & (xlib::ShiftMask
+                | xlib::ControlMask
+                | xlib::Mod1Mask
+                | xlib::Mod2Mask
+                | xlib::Mod3Mask
+                | xlib::Mod4Mask
+                | xlib::Mod5Mask)
+    }
+    // Every combination of the lock bits added to the modifier.
+    fn with_lock_combinations(&self, modifier: u32) -> Vec<u32> {
+        if modifier == xlib::AnyModifier {
+            return vec![modifier];
+        }
+        let bits: Vec<u32> = (0..8)
+            .map(|i| 1 << i)
+            .filter(|bit| self.lock_masks & bit != 0)
+            .collect();
+        (0..1u32 << bits.len())
+            .map(|combination| {
+                bits.iter()
+                    .enumerate()
+                    .filter(|(i, _)| combination & (1 << i) != 0)
+                    .fold(modifier, |m, (_, bit)| m | bit)
+            })
+            .collect()
+    }
+    // NumLock and ScrollLock are not fixed modifiers, they have
+    // to be looked up in the modifier mapping.
+    fn load_lock_masks(&mut self) {
+        let num_lock = self.modifier_mask_of("Num_Lock");
+        let scroll_lock = self.modifier_mask_of("Scroll_Lock");
+        self.lock_masks = xlib::LockMask | num_lock | scroll_lock;
+        trace!("lock modifiers mask: {}", self.lock_masks);
+    }
+    fn modifier_mask_of(&self, keysym_name: &str) -> u32 {
+        let Some(keycode) = X11Adapter::keysym_from_name(keysym_name)
+            .and_then(|keysym| self.keycode_from_keysym(keysym))
+        else {
+            return 0;
+        };
+        let mut mask = 0;
+        unsafe {
+            let modmap = xlib::XGetModifierMapping(self.display);
+            let per_modifier = (*modmap).max_keypermod as usize;
+            let keycodes = slice::from_raw_parts((*modmap).modifiermap, 8 * per_modifier);
+            for (i, code) in keycodes.iter().enumerate() {
+                if *code as tdawm::Keycode == keycode {
+                    mask |= 1 << (i / per_modifier);
+                }
+            }
+            xlib::XFreeModifiermap(modmap);
+        }
+        mask
+    }
+
+    /// Resolves a keysym name such as "Return" or "h".
+    pub fn keysym_from_name(name: &str) -> Option<tdawm::Keysym> {
+        let name = CString::new(name).ok()?;
+        let keysym = unsafe { xlib::XStringToKeysym(name.as_ptr()) };
+        if keysym == xlib::NoSymbol as tdawm::Keysym {
+            return None;
+        }
+        Some(keysym)
+    }
+    /// Keycode currently producing this keysym with the active keyboard mapping.
+    pub fn keycode_from_keysym(&self, keysym: tdawm::Keysym) -> Option<tdawm::Keycode> {
+        let keycode = unsafe { xlib::XKeysymToKeycode(self.display, keysym) };
+        if keycode == 0 {
+            return None;
+        }
+        Some(keycode as tdawm::Keycode)
+    }
+    /// Unshifted keysym of a keycode with the active keyboard mapping.
+    pub fn keysym_from_keycode(&self, keycode: tdawm::Keycode) -> tdawm::Keysym {
+        unsafe { xlib::XkbKeycodeToKeysym(self.display, keycode as u8, 0, 0) }
+    }
+    /// Must be called when a MappingNotify event is received
+    /// so keysym/keycode conversions use the new mapping.
+    pub fn refresh_keyboard_mapping(&mut self, event: &mut xlib::XMappingEvent) {
+        trace!("refreshing keyboard mapping");
+        unsafe {
+            xlib::XRefreshKeyboardMapping(event);
+        }
+        self.load_lock_masks();
+    }
 
     pub fn ungrab_key(&self, keycode: tdawm::Keycode, modifier: u32) {
         trace!("ungrabbing key {} with modifier {}", keycode, modifier);
-        unsafe {
-            xlib::XUngrabKey(
-                self.display,
-                keycode,
-                modifier,
-                xlib::XDefaultRootWindow(self.display),
-            );
+        for modifier in self.with_lock_combinations(modifier) {
+            unsafe {
+                xlib::XUngrabKey(
+                    self.display,
+                    keycode,
+                    modifier,
+                    xlib::XDefaultRootWindow(self.display),
+                );
+            }
         }
     }
     pub fn focus_window(&self, window_id: WindowId) {
@@ -128,6 +338,177 @@ impl X11Adapter {
         trace!("resizing window {} to {}x{}", window_id, width, height);
         unsafe { xlib::XResizeWindow(self.display, window_id, width, height) };
     }
+    /// Tells if the window lists this protocol (WM_DELETE_WINDOW, ...)
+    /// in its WM_PROTOCOLS property.
+    pub fn supports_protocol(&mut self, window_id: WindowId, protocol: &'static str) -> bool {
+        let protocol = self.atom_manager.get_atom(protocol, self.display);
+        let mut protocols: *mut Atom = ptr::null_mut();
+        let mut count = 0;
+        unsafe {
+            if xlib::XGetWMProtocols(self.display, window_id, &mut protocols, &mut count) == 0 {
+                return false;
+            }
+            let supported = slice::from_raw_parts(protocols, count as usize).contains(&protocol);
+            xlib::XFree(protocols as *mut _);
+            supported
+        }
+    }
+    /// Sends a ClientMessage of format 32 to the window.
+    pub fn send_client_message(
+        &mut self,
+        window_id: WindowId,
+        message_type: &'static str,
+        data: [c_long; 5],
+    ) {
+        let message_type = self.atom_manager.get_atom(message_type, self.display);
+        unsafe {
+            let mut event: xlib::XClientMessageEvent = zeroed();
+            event.type_ = xlib::ClientMessage;
+            event.window = window_id;
+            event.message_type = message_type;
+            event.format = 32;
+            event.data = xlib::ClientMessageData::from(data);
+            let mut event = xlib::XEvent::from(event);
+            xlib::XSendEvent(self.display, window_id, 0, xlib::NoEventMask, &mut event);
+        }
+    }
+    /// Asks the window to follow one of the WM_PROTOCOLS it supports.
+    pub fn send_protocol(&mut self, window_id: WindowId, protocol: &'static str) {
+        let protocol = self.atom_manager.get_atom(protocol, self.display);
+        self.send_client_message(
+            window_id,
+            "WM_PROTOCOLS",
+            [protocol as c_long, xlib::CurrentTime as c_long, 0, 0, 0],
+        );
+    }
+    /// Size of the window when its WM_NORMAL_HINTS give the same
+    /// minimum and maximum size.
+    pub fn get_fixed_size_hint(&self, window_id: WindowId) -> Option<Size> {
+        let mut supplied: c_long = 0;
+        unsafe {
+            let mut hints: xlib::XSizeHints = zeroed();
+            if xlib::XGetWMNormalHints(self.display, window_id, &mut hints, &mut supplied) == 0 {
+                return None;
+            }
+            let both = xlib::PMinSize | xlib::PMaxSize;
+            if hints.flags & both != both
+                || hints.min_width <= 0
+                || hints.min_height <= 0
+                || hints.min_width != hints.max_width
+                || hints.min_height != hints.max_height
+            {
+                return None;
+            }
+            Some(Size {
+                x: hints.min_width as u32,
+                y: hints.min_height as u32,
+            })
+        }
+    }
+    /// Tells if the _NET_WM_STATE property of the window holds this state.
+    pub fn has_wm_state(&mut self, window_id: WindowId, state: &'static str) -> bool {
+        let state = self.atom_manager.get_atom(state, self.display);
+        self.get_wm_states(window_id).contains(&state)
+    }
+    /// Adds a state to the _NET_WM_STATE property of the window,
+    /// the other states are kept.
+    pub fn add_wm_state(&mut self, window_id: WindowId, state: &'static str) {
+        let state = self.atom_manager.get_atom(state, self.display);
+        let mut states = self.get_wm_states(window_id);
+        if !states.contains(&state) {
+            states.push(state);
+            self.set_wm_states(window_id, &states);
+        }
+    }
+    /// Removes a state from the _NET_WM_STATE property of the window,
+    /// the other states are kept.
+    pub fn remove_wm_state(&mut self, window_id: WindowId, state: &'static str) {
+        let state = self.atom_manager.get_atom(state, self.display);
+        let mut states = self.get_wm_states(window_id);
+        if states.contains(&state) {
+            states.retain(|s| *s != state);
+            self.set_wm_states(window_id, &states);
+        }
+    }
+    fn get_wm_states(&mut self, window_id: WindowId) -> Vec<Atom> {
+        let net_wm_state = self.atom_manager.get_atom("_NET_WM_STATE", self.display);
+        let mut actual_type_return: Atom = 0;
+        let mut actual_format_return: i32 = 0;
+        let mut nitems_return: u64 = 0;
+        let mut bytes_after_return: u64 = 0;
+        let mut prop_return: *mut c_uchar = ptr::null_mut();
+        unsafe {
+            if xlib::XGetWindowProperty(
+                self.display,
+                window_id,
+                net_wm_state,
+                0,
+                32,
+                0,
+                xlib::XA_ATOM,
+                &mut actual_type_return,
+                &mut actual_format_return,
+                &mut nitems_return,
+                &mut bytes_after_return,
+                &mut prop_return,
+            ) != xlib::Success as i32
+                || prop_return.is_null()
+            {
+                return vec![];
+            }
+            let states =
+                slice::from_raw_parts(prop_return as *const Atom, nitems_return as usize).to_vec();
+            xlib::XFree(prop_return as *mut _);
+            states
+        }
+    }
+    fn set_wm_states(&mut self, window_id: WindowId, states: &[Atom]) {
+        let net_wm_state = self.atom_manager.get_atom("_NET_WM_STATE", self.display);
+        unsafe {
+            xlib::XChangeProperty(
+                self.display,
+                window_id,
+                net_wm_state,
+                xlib::XA_ATOM,
+                32,
+                xlib::PropModeReplace,
+                states.as_ptr() as *const u8,
+                states.len() as i32,
+            );
+        }
+    }
+    /// Closes the connection of the client owning the window.
+    pub fn kill_client(&self, window_id: WindowId) {
+        unsafe { xlib::XKillClient(self.display, window_id) };
+    }
+    pub fn get_window_geometry(&self, window_id: WindowId) -> Option<(Position, Size)> {
+        let mut root = 0;
+        let (mut x, mut y) = (0, 0);
+        let (mut width, mut height, mut border, mut depth) = (0, 0, 0, 0);
+        let status = unsafe {
+            xlib::XGetGeometry(
+                self.display,
+                window_id,
+                &mut root,
+                &mut x,
+                &mut y,
+                &mut width,
+                &mut height,
+                &mut border,
+                &mut depth,
+            )
+        };
+        if status == 0 {
+            return None;
+        }
+        Some((
+            Position { x, y },
+            Size {
+                x: width,
+                y: height,
+            },
+        ))
+    }
     pub fn hide_window(&self, window_id: WindowId) {
         unsafe { xlib::XUnmapWindow(self.display, window_id) };
     }
@@ -154,6 +535,66 @@ impl X11Adapter {
     pub fn show_window(&self, window_id: WindowId) {
         unsafe { xlib::XMapWindow(self.display, window_id) };
     }
+    // Clients only use the EWMH hints a window manager says it supports,
+    // and only trust the list when the _NET_SUPPORTING_WM_CHECK window exists.
+    fn ewmh_init(&mut self) {
+        let supported: Vec<Atom> = [
+            "_NET_SUPPORTED",
+            "_NET_SUPPORTING_WM_CHECK",
+            "_NET_CURRENT_DESKTOP",
+            "_NET_WM_WINDOW_TYPE",
+            "_NET_WM_WINDOW_TYPE_DOCK",
+            "_NET_WM_STATE",
+            "_NET_WM_STATE_FULLSCREEN",
+        ]
+        .into_iter()
+        .map(|name| self.atom_manager.get_atom(name, self.display))
+        .collect();
+        let net_supported = self.atom_manager.get_atom("_NET_SUPPORTED", self.display);
+        let check = self
+            .atom_manager
+            .get_atom("_NET_SUPPORTING_WM_CHECK", self.display);
+        let net_wm_name = self.atom_manager.get_atom("_NET_WM_NAME", self.display);
+        let utf8 = self.atom_manager.get_atom("UTF8_STRING", self.display);
+        let name = "tdawm";
+        unsafe {
+            let check_window =
+                xlib::XCreateSimpleWindow(self.display, self.root_window, 0, 0, 1, 1, 0, 0, 0);
+            for window in [self.root_window, check_window] {
+                xlib::XChangeProperty(
+                    self.display,
+                    window,
+                    check,
+                    xlib::XA_WINDOW,
+                    32,
+                    xlib::PropModeReplace,
+                    &check_window as *const WindowId as *const u8,
+                    1,
+                );
+            }
+            xlib::XChangeProperty(
+                self.display,
+                check_window,
+                net_wm_name,
+                utf8,
+                8,
+                xlib::PropModeReplace,
+                name.as_ptr(),
+                name.len() as i32,
+            );
+            xlib::XChangeProperty(
+                self.display,
+                self.root_window,
+                net_supported,
+                xlib::XA_ATOM,
+                32,
+                xlib::PropModeReplace,
+                supported.as_ptr() as *const u8,
+                supported.len() as i32,
+            );
+        }
+    }
+
     pub fn ewmh_set_current_desktop(&mut self, index: usize) {
         let data: u32 = index as u32;
         let format = 32;
@@ -174,6 +615,91 @@ impl X11Adapter {
         }
     }
 
+    /// Publishes the active binding mode in the _TDAWM_MODE property
+    /// of the root window so status bars can display it.
+    pub fn set_mode_property(&mut self, mode: &str) {
+        unsafe {
+            let prop = self.atom_manager.get_atom("_TDAWM_MODE", self.display);
+            let utf8 = self.atom_manager.get_atom("UTF8_STRING", self.display);
+            xlib::XChangeProperty(
+                self.display,
+                self.root_window,
+                prop,
+                utf8,
+                8,
+                xlib::PropModeReplace,
+                mode.as_ptr(),
+                mode.len() as i32,
+            );
+        }
+    }
+
+    /// Sends a command to the running tdawm, commands are appended
+    /// to the _TDAWM_COMMAND property of the root window and
+    /// separated by a NUL byte.
+    pub fn send_command(&mut self, command: &str) {
+        let data = format!("{}\0", command);
+        unsafe {
+            let prop = self.atom_manager.get_atom("_TDAWM_COMMAND", self.display);
+            let utf8 = self.atom_manager.get_atom("UTF8_STRING", self.display);
+            xlib::XChangeProperty(
+                self.display,
+                self.root_window,
+                prop,
+                utf8,
+                8,
+                xlib::PropModeAppend,
+                data.as_ptr(),
+                data.len() as i32,
+            );
+            xlib::XSync(self.display, 0);
+        }
+    }
+    pub fn is_command_property(&mut self, atom: Atom) -> bool {
+        atom == self.atom_manager.get_atom("_TDAWM_COMMAND", self.display)
+    }
+    /// Reads and deletes the commands sent with `send_command`.
+    pub fn take_commands(&mut self) -> Vec<String> {
+        let mut actual_type_return: Atom = 0;
+        let mut actual_format_return: i32 = 0;
+        let mut nitems_return: u64 = 0;
+        let mut bytes_after_return: u64 = 0;
+        let mut prop_return: *mut c_uchar = ptr::null_mut();
+        unsafe {
+            let prop = self.atom_manager.get_atom("_TDAWM_COMMAND", self.display);
+            if xlib::XGetWindowProperty(
+                self.display,
+                self.root_window,
+                prop,
+                0,
+                c_long::MAX,
+                1,
+                xlib::AnyPropertyType as u64,
+                &mut actual_type_return,
+                &mut actual_format_return,
+                &mut nitems_return,
+                &mut bytes_after_return,
+                &mut prop_return,
+            ) != xlib::Success as i32
+                || prop_return.is_null()
+            {
+                return vec![];
+            }
+            let bytes = slice::from_raw_parts(prop_return, nitems_return as usize);
+            let commands = bytes
+                .split(|b| *b == 0)
+                .filter(|c| !c.is_empty())
+                .map(|c| String::from_utf8_lossy(c).into_owned())
+                .collect();
+            xlib::XFree(prop_return as *mut _);
+            commands
+        }
+    }
+
+    /// Moves the pointer to a position relative to the window.
+    pub fn warp_pointer(&self, window_id: WindowId, x: i32, y: i32) {
+        unsafe { xlib::XWarpPointer(self.display, 0, window_id, 0, 0, 0, 0, x, y) };
+    }
     pub fn get_mouse_position(&self) -> (i16, i16) {
         unsafe {
             // Variables to store mouse position
@@ -202,6 +728,25 @@ impl X11Adapter {
     }
 }
 
+extern "C" fn on_x_error(display: *mut xlib::Display, event: *mut xlib::XErrorEvent) -> i32 {
+    let event = unsafe { &*event };
+    let mut text = [0 as c_char; 256];
+    unsafe {
+        xlib::XGetErrorText(
+            display,
+            event.error_code as i32,
+            text.as_mut_ptr(),
+            text.len() as i32,
+        );
+    }
+    let text = unsafe { CStr::from_ptr(text.as_ptr()) }.to_string_lossy();
+    warn!(
+        "x11 error: {} (request {}, resource {})",
+        text, event.request_code, event.resourceid
+    );
+    0
+}
+
 // EWMH utilities functions
 impl Window {
     pub fn get_window_type(&self, server: &mut X11Adapter) -> WindowType {
@@ -242,7 +787,7 @@ impl Window {
             if window_type as u64 == net_wm_window_dock_atom {
                 return WindowType::Dock;
             }
-            return WindowType::Normal;
+            WindowType::Normal
         }
     }
 }
@@ -264,14 +809,14 @@ impl AtomManager {
         unsafe {
             let atom = xlib::XInternAtom(display, format!("{}\0", name).as_ptr() as *const i8, 0);
             self.atoms.insert(name, atom);
-            return atom;
+            atom
         }
     }
     pub fn identify(&self, atom: u64, display: *mut xlib::_XDisplay) -> &'static str {
         unsafe {
             let val = xlib::XGetAtomName(display, atom);
             let c_str = CStr::from_ptr(val);
-            return c_str.to_str().unwrap();
+            c_str.to_str().unwrap()
         }
     }
 }